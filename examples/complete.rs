@@ -10,30 +10,15 @@ impl mau::AppSetup for App {
     type Strings = ();
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, mau::AppConfig)]
+#[app_name = "mau-example-complete"]
 pub struct Config {
+    #[language]
     language: String,
+    #[window]
     window: Option<WindowConfig>,
 }
 
-impl mau::AppConfig for Config {
-    fn app_name() -> &'static str {
-        "mau-example-complete"
-    }
-
-    fn language(&self) -> &str {
-        &self.language
-    }
-
-    fn window_config(&self) -> &Option<mau::config::WindowConfig> {
-        &self.window
-    }
-
-    fn window_config_mut(&mut self) -> &mut Option<mau::config::WindowConfig> {
-        &mut self.window
-    }
-}
-
 impl Default for Config {
     fn default() -> Self {
         Self {
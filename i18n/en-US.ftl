@@ -0,0 +1,85 @@
+# Built-in translations for mau. These are merged into every language loaded through a
+# LanguageMap, and can be overridden by defining the same messages in the app's own FTL files.
+
+failure = An error occurred: { $message }
+
+## Config errors
+
+error-config-io = I/O error: { $error }
+error-config-no-config-dir = Cannot determine where to store the config, because the user's home directory is unknown. Set the MAU_CONFIG_DIR environment variable to choose a directory.
+error-config-toml-serialize = TOML serialization error: { $error }
+error-config-toml-deserialize = TOML deserialization error: { $error }
+error-config-deserialize =
+    The config file could not be read: { $error }
+    It was moved to { $moved-to } so that you can fix it; a fresh config will be created the next time the app is started.
+error-config-json = JSON error: { $error }
+error-config-ron-deserialize = RON deserialization error: { $error }
+error-config-ron-serialize = RON serialization error: { $error }
+error-config-invalid-layer = Invalid config value in the { $layer ->
+        [default] default config
+        [system] system-wide config
+        [inherited] inherited profile
+        [environment] environment variables
+        [override] overrides
+       *[user] user config
+    }: { $error }
+error-config-invalid-version = Config file has an invalid version number
+error-config-unsupported-version = Config file has version { $found }, which is newer than the newest supported version { $supported }. Was it saved by a newer version of the app?
+error-config-migration = Migrating config from version { $from } failed: { $error }
+error-config-migration-count = The app defines { $migrations } config migrations, but its config version is { $version }. This is a bug in the app
+error-config-invalid-profile-name = Invalid profile name "{ $name }". Profile names may only contain letters, digits, spaces, dashes, and underscores
+error-config-unknown-profile = Profile "{ $name }" does not exist
+error-config-profile-exists = Profile "{ $name }" already exists
+error-config-profile-in-use = Profile "{ $name }" is currently in use
+error-config-profile-inheritance-cycle = Profile "{ $name }" inherits from itself
+error-config-config-is-already-loaded = The config was already loaded
+
+## Storage errors
+
+error-storage-io = I/O error: { $error }
+error-storage-no-storage-dir = Cannot determine where to store app data, because the user's home directory is unknown. Set the MAU_DATA_DIR and MAU_CACHE_DIR environment variables to choose directories.
+error-storage-toml-serialize = TOML serialization error: { $error }
+error-storage-toml-deserialize = TOML deserialization error: { $error }
+error-storage-invalid-value = Stored value "{ $key }" has an unexpected type: { $error }
+error-storage-invalid-name = Invalid storage name "{ $name }". Names must be plain file names, without path separators
+
+## Clipboard errors
+
+error-clipboard-uninitialized = Clipboard content is uninitialized
+error-clipboard-save-failed = Saving to clipboard failed: { $error }
+error-clipboard-does-not-contain-text = Clipboard does not contain text
+error-clipboard-does-not-contain-an-image = Clipboard does not contain an image
+error-clipboard-does-not-contain-format = Clipboard does not contain { $format }
+error-clipboard-content-unavailable = Clipboard content is unavailable
+error-clipboard-not-supported = Clipboard is not supported on your platform
+error-clipboard-occupied = Clipboard is occupied by another application. Try again
+error-clipboard-conversion-failed = Cannot convert data to/from a clipboard-specific format. Try again or report a bug
+error-clipboard-unknown = Unknown clipboard error: { $error }
+
+## Language errors
+
+error-language-no-translations = No translations available for language { $language }
+error-language-invalid-ftl = Language { $language } has an invalid FTL translation file
+
+## Image errors
+
+error-image-io = I/O error: { $error }
+error-image-unknown-format = Unrecognized image format
+error-image-format-disabled = Support for { $format } images is not enabled
+error-image-encoding-not-supported = { $format } images cannot be encoded
+error-image-decode = Cannot decode image: { $error }
+error-image-encode = Cannot encode image: { $error }
+
+## Config validation
+
+config-invalid-out-of-range = Must be between { $min } and { $max }
+config-invalid-not-positive = Must be greater than zero
+config-invalid-unknown-choice = Unknown choice "{ $value }"
+config-invalid-unknown-language = Unknown language "{ $code }"
+
+## Settings screen
+
+settings-title = Settings
+settings-back = Back
+settings-reset = Reset
+settings-reset-all = Reset all
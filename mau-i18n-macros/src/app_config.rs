@@ -0,0 +1,326 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Data, DeriveInput, Fields, GenericArgument, Lit, LitInt, LitStr, Meta, NestedMeta,
+    Path, PathArguments, Type,
+};
+
+use crate::error::Error;
+
+/// Overrides of the trait's provided items, specified with `#[config(...)]`.
+#[derive(Default)]
+struct ConfigOptions {
+    version: Option<LitInt>,
+    format: Option<Ident>,
+    migrations: Option<Path>,
+    validate: Option<Path>,
+    shared_keys: Option<Vec<LitStr>>,
+}
+
+pub(crate) fn implementation(ast: &DeriveInput) -> Result<TokenStream, Error> {
+    let app_name = app_name(&ast.attrs)?
+        .ok_or_else(|| Error::new(ast.ident.span(), "missing #[app_name = \"...\"] attribute"))?;
+
+    let fields = if let Data::Struct(s) = &ast.data {
+        match &s.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new(s.fields.span(), "struct must have named fields")),
+        }
+    } else {
+        return Err(Error::new(ast.span(), "struct expected"));
+    };
+
+    let mut language = None;
+    let mut window = None;
+    for field in fields {
+        let name = field.ident.as_ref().unwrap();
+        for attr in &field.attrs {
+            let slot = if attr.path.is_ident("language") {
+                &mut language
+            } else if attr.path.is_ident("window") {
+                &mut window
+            } else {
+                continue;
+            };
+            if !attr.tokens.is_empty() {
+                return Err(Error::new(
+                    attr.tokens.span(),
+                    "attribute takes no arguments",
+                ));
+            }
+            if slot.is_some() {
+                return Err(Error::new(
+                    attr.span(),
+                    "only one field can be marked with this attribute",
+                ));
+            }
+            *slot = Some((name, &field.ty));
+        }
+    }
+
+    let (language, language_type) = language.ok_or_else(|| {
+        Error::new(
+            ast.ident.span(),
+            "missing a field marked with #[language], which holds the language code",
+        )
+    })?;
+    if !is_path_to(language_type, "String") {
+        return Err(Error::new(
+            language_type.span(),
+            "the #[language] field must be a String",
+        ));
+    }
+    let (window, window_type) = window.ok_or_else(|| {
+        Error::new(
+            ast.ident.span(),
+            "missing a field marked with #[window], which holds the window's position and size",
+        )
+    })?;
+    if !option_inner(window_type).is_some_and(|inner| is_path_to(inner, "WindowConfig")) {
+        return Err(Error::new(
+            window_type.span(),
+            "the #[window] field must be an Option<WindowConfig>",
+        ));
+    }
+
+    let options = config_options(&ast.attrs)?;
+    Ok(implement_trait(ast, &app_name, language, window, &options))
+}
+
+/// Parses the `#[config(...)]` attributes.
+///
+/// ```ignore
+/// #[config(
+///     version = 2,
+///     migrations = "migrations",
+///     validate = "Config::check",
+///     format = "Json",
+///     shared_keys("window", "recent_files"),
+/// )]
+/// ```
+fn config_options(attrs: &[Attribute]) -> Result<ConfigOptions, Error> {
+    let mut options = ConfigOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("config")) {
+        let meta = attr
+            .parse_meta()
+            .map_err(|e| Error::new(e.span(), &e.to_string()))?;
+        let list = match meta {
+            Meta::List(list) => list,
+            meta => {
+                return Err(Error::new(
+                    meta.span(),
+                    "expected #[config(option = value, ...)]",
+                ))
+            }
+        };
+        for nested in &list.nested {
+            let meta = match nested {
+                NestedMeta::Meta(meta) => meta,
+                NestedMeta::Lit(lit) => return Err(Error::new(lit.span(), "option expected")),
+            };
+            let name = meta
+                .path()
+                .get_ident()
+                .map(Ident::to_string)
+                .unwrap_or_default();
+            match (name.as_str(), meta) {
+                ("version", Meta::NameValue(meta)) => match &meta.lit {
+                    Lit::Int(version) => {
+                        version
+                            .base10_parse::<u32>()
+                            .map_err(|e| Error::new(version.span(), &e.to_string()))?;
+                        options.version = Some(version.clone());
+                    }
+                    lit => return Err(Error::new(lit.span(), "version must be an integer")),
+                },
+                ("format", Meta::NameValue(meta)) => {
+                    let format = string(&meta.lit)?;
+                    options.format =
+                        Some(format_ident!("{}", format.value(), span = format.span()));
+                }
+                ("migrations", Meta::NameValue(meta)) => {
+                    options.migrations = Some(path(&meta.lit)?);
+                }
+                ("validate", Meta::NameValue(meta)) => {
+                    options.validate = Some(path(&meta.lit)?);
+                }
+                ("shared_keys", Meta::List(list)) => {
+                    let keys = list
+                        .nested
+                        .iter()
+                        .map(|nested| match nested {
+                            NestedMeta::Lit(lit) => string(lit),
+                            other => Err(Error::new(other.span(), "key string expected")),
+                        })
+                        .collect::<Result<_, _>>()?;
+                    options.shared_keys = Some(keys);
+                }
+                ("version" | "format" | "migrations" | "validate", _) => {
+                    return Err(Error::new(
+                        meta.span(),
+                        &format!("expected `{} = ...`", name),
+                    ))
+                }
+                ("shared_keys", _) => {
+                    return Err(Error::new(
+                        meta.span(),
+                        "expected `shared_keys(\"key\", ...)`",
+                    ))
+                }
+                _ => {
+                    return Err(Error::new(
+                        meta.path().span(),
+                        "unknown option; expected one of `version`, `format`, `migrations`, \
+                         `validate`, `shared_keys`",
+                    ))
+                }
+            }
+        }
+    }
+
+    let has_version = options
+        .version
+        .as_ref()
+        .is_some_and(|version| version.base10_digits() != "0");
+    if has_version && options.migrations.is_none() {
+        return Err(Error::new(
+            options.version.as_ref().unwrap().span(),
+            "configs with a version need migrations; add `migrations = \"path\"`",
+        ));
+    }
+    Ok(options)
+}
+
+fn string(lit: &Lit) -> Result<LitStr, Error> {
+    match lit {
+        Lit::Str(s) => Ok(s.clone()),
+        lit => Err(Error::new(lit.span(), "string expected")),
+    }
+}
+
+/// Parses a path given as a string, like serde's `#[serde(default = "path")]`.
+fn path(lit: &Lit) -> Result<Path, Error> {
+    let s = string(lit)?;
+    s.parse()
+        .map_err(|e| Error::new(s.span(), &format!("invalid path: {}", e)))
+}
+
+/// Finds the `#[app_name = "..."]` attribute.
+fn app_name(attrs: &[Attribute]) -> Result<Option<LitStr>, Error> {
+    for attr in attrs {
+        if !attr.path.is_ident("app_name") {
+            continue;
+        }
+        let meta = attr
+            .parse_meta()
+            .map_err(|e| Error::new(e.span(), &e.to_string()))?;
+        return match meta {
+            Meta::NameValue(meta) => match meta.lit {
+                Lit::Str(s) if !s.value().is_empty() => Ok(Some(s)),
+                lit => Err(Error::new(
+                    lit.span(),
+                    "app name must be a non-empty string",
+                )),
+            },
+            meta => Err(Error::new(
+                meta.span(),
+                "app name must be specified as #[app_name = \"...\"]",
+            )),
+        };
+    }
+    Ok(None)
+}
+
+/// Returns whether the type is a path whose last segment is the given name, without any generic
+/// arguments.
+fn is_path_to(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name && segment.arguments.is_empty()),
+        _ => false,
+    }
+}
+
+/// Returns the `T` in `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn implement_trait(
+    ast: &DeriveInput,
+    app_name: &LitStr,
+    language: &Ident,
+    window: &Ident,
+    options: &ConfigOptions,
+) -> TokenStream {
+    let typ = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let version = options.version.as_ref().map(|version| {
+        quote! { const VERSION: u32 = #version; }
+    });
+    let format = options.format.as_ref().map(|format| {
+        quote! { const FORMAT: ::mau::config::Format = ::mau::config::Format::#format; }
+    });
+    let shared_keys = options.shared_keys.as_ref().map(|keys| {
+        quote! { const SHARED_KEYS: &'static [&'static str] = &[#(#keys),*]; }
+    });
+    let migrations = options.migrations.as_ref().map(|migrations| {
+        quote! {
+            fn migrations() -> &'static [::mau::config::Migration] {
+                #migrations()
+            }
+        }
+    });
+    let validate = options.validate.as_ref().map(|validate| {
+        quote! {
+            fn validate(&self) -> ::std::vec::Vec<::mau::config::InvalidField> {
+                #validate(self)
+            }
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::mau::AppConfig for #typ #ty_generics #where_clause {
+            #version
+            #format
+            #shared_keys
+
+            fn app_name() -> &'static str {
+                #app_name
+            }
+
+            fn language(&self) -> &str {
+                &self.#language
+            }
+
+            fn window_config(&self) -> &Option<::mau::config::WindowConfig> {
+                &self.#window
+            }
+
+            fn window_config_mut(&mut self) -> &mut Option<::mau::config::WindowConfig> {
+                &mut self.#window
+            }
+
+            #migrations
+            #validate
+        }
+    }
+}
@@ -11,15 +11,18 @@ pub fn pascal_case_to_kebab_case(s: &str) -> String {
         return result;
     }
 
-    result.extend(s.chars().next().unwrap().to_lowercase());
-
-    for c in s.chars().skip(1) {
-        if c.is_uppercase() {
-            result.push('-');
-            result.extend(c.to_lowercase());
-        } else {
-            result.push(c);
+    // Runs of uppercase letters are acronyms and form a single word, eg. `InvalidFTL` becomes
+    // `invalid-ftl` and `FTLFile` becomes `ftl-file`.
+    let chars: Vec<char> = s.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            let previous = chars[i - 1];
+            let next_is_lowercase = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if !previous.is_uppercase() || next_is_lowercase {
+                result.push('-');
+            }
         }
+        result.extend(c.to_lowercase());
     }
     result
 }
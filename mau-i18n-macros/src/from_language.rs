@@ -7,6 +7,7 @@ use syn::{Data, DeriveInput, Expr, FieldValue, Fields, Member, Token, Type};
 
 use crate::common::snake_case_to_kebab_case;
 use crate::error::Error;
+use crate::ftl::FtlFile;
 
 struct FieldPairs {
     pairs: Vec<(Ident, Type)>,
@@ -28,12 +29,52 @@ pub(crate) fn implementation(ast: &DeriveInput) -> Result<TokenStream, Error> {
             }
             fields.pairs.push((name.clone(), field.ty.clone()));
         }
-        implement_trait(&ast.ident, &fields)
+        let ftl = FtlFile::from_attributes(&ast.attrs)?;
+        let errors = match &ftl {
+            Some(ftl) => validate_keys(ftl, &fields),
+            None => Vec::new(),
+        };
+        let mut output = implement_trait(&ast.ident, &fields)?;
+        if let Some(ftl) = &ftl {
+            output.extend(ftl.track());
+        }
+        for error in errors {
+            output.extend(error.emit());
+        }
+        Ok(output)
     } else {
         Err(Error::new(ast.span(), "struct expected"))
     }
 }
 
+/// Checks the fields' keys against the FTL file, treating them as top-level messages.
+///
+/// Strings must resolve to a message value that doesn't use any variables. Formatted messages must
+/// resolve to a message value, and nested structs to a message. Maps are not checked, since their
+/// keys are only known at runtime.
+fn validate_keys(ftl: &FtlFile, fields: &FieldPairs) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for (name, typ) in &fields.pairs {
+        let key = snake_case_to_kebab_case(&name.to_string());
+        let type_name = match typ {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        let result = match type_name.as_deref() {
+            Some("String") => ftl.check_pattern(&key, Some(&[]), name.span()),
+            Some("Formatted") => ftl.check_pattern(&key, None, name.span()),
+            Some("Map") | None => Ok(()),
+            Some(_) => ftl.check_message(&key, name.span()),
+        };
+        errors.extend(result.err());
+    }
+    errors
+}
+
 fn implement_trait(typ: &Ident, fields: &FieldPairs) -> Result<TokenStream, Error> {
     let mut ctor_fields = Punctuated::<FieldValue, Comma>::new();
     let language = Ident::new("language", Span::call_site());
@@ -0,0 +1,196 @@
+//! Compile-time validation of generated keys against an FTL file, enabled with `#[ftl("path")]`.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use fluent_syntax::ast::Entry;
+use mau_i18n_syntax::pattern_variables;
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Attribute, Lit, Meta, NestedMeta};
+
+use crate::error::Error;
+
+/// The variables referenced by a message's value and attributes.
+struct MessageInfo {
+    value: Option<BTreeSet<String>>,
+    attributes: HashMap<String, BTreeSet<String>>,
+}
+
+/// A parsed FTL file that keys are checked against.
+pub(crate) struct FtlFile {
+    path: PathBuf,
+    messages: HashMap<String, MessageInfo>,
+}
+
+impl FtlFile {
+    /// Parses the `#[ftl("path")]` attribute, if present, and loads the file it points to.
+    ///
+    /// The path is relative to the crate's manifest directory.
+    pub(crate) fn from_attributes(attrs: &[Attribute]) -> Result<Option<Self>, Error> {
+        let attr = match attrs.iter().find(|attr| attr.path.is_ident("ftl")) {
+            Some(attr) => attr,
+            None => return Ok(None),
+        };
+        let meta = attr
+            .parse_meta()
+            .map_err(|e| Error::new(e.span(), &e.to_string()))?;
+        let path = match &meta {
+            Meta::List(list) if list.nested.len() == 1 => match &list.nested[0] {
+                NestedMeta::Lit(Lit::Str(path)) => path,
+                other => return Err(Error::new(other.span(), "path string expected")),
+            },
+            _ => return Err(Error::new(meta.span(), "expected #[ftl(\"path\")]")),
+        };
+
+        let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+        let full_path = PathBuf::from(manifest_dir).join(path.value());
+        let source = std::fs::read_to_string(&full_path).map_err(|error| {
+            Error::new(
+                path.span(),
+                &format!("cannot read {}: {}", full_path.display(), error),
+            )
+        })?;
+        let resource = fluent_syntax::parser::parse(source.as_str()).map_err(|(_, errors)| {
+            let error = &errors[0];
+            let line = source[..error.pos.start].lines().count().max(1);
+            Error::new(
+                path.span(),
+                &format!(
+                    "syntax error in {} on line {}: {:?}",
+                    full_path.display(),
+                    line,
+                    error.kind
+                ),
+            )
+        })?;
+
+        let mut messages = HashMap::new();
+        for entry in &resource.body {
+            if let Entry::Message(message) = entry {
+                let info = MessageInfo {
+                    value: message.value.as_ref().map(pattern_variables),
+                    attributes: message
+                        .attributes
+                        .iter()
+                        .map(|attribute| {
+                            (
+                                attribute.id.name.to_owned(),
+                                pattern_variables(&attribute.value),
+                            )
+                        })
+                        .collect(),
+                };
+                messages.insert(message.id.name.to_owned(), info);
+            }
+        }
+        Ok(Some(Self {
+            path: full_path,
+            messages,
+        }))
+    }
+
+    /// Checks that a message exists, without requiring it to have a value.
+    pub(crate) fn check_message(&self, key: &str, span: Span) -> Result<(), Error> {
+        self.messages
+            .get(key)
+            .map(|_| ())
+            .ok_or_else(|| self.missing(key, span))
+    }
+
+    /// Checks that the key resolves to a pattern, and that all variables the pattern references are
+    /// among `variables`, if given. The key can refer to an attribute, like in `Language::message`.
+    pub(crate) fn check_pattern(
+        &self,
+        key: &str,
+        variables: Option<&[String]>,
+        span: Span,
+    ) -> Result<(), Error> {
+        let (message_name, attribute_name) = match key.split_once('.') {
+            Some((message_name, attribute_name)) => (message_name, Some(attribute_name)),
+            None => (key, None),
+        };
+        let message = self
+            .messages
+            .get(message_name)
+            .ok_or_else(|| self.missing(message_name, span))?;
+        let used = match attribute_name {
+            Some(attribute_name) => message.attributes.get(attribute_name).ok_or_else(|| {
+                Error::new(
+                    span,
+                    &format!(
+                        "message `{}` in {} does not have the attribute `{}`",
+                        message_name,
+                        self.path.display(),
+                        attribute_name
+                    ),
+                )
+            })?,
+            None => message.value.as_ref().ok_or_else(|| {
+                Error::new(
+                    span,
+                    &format!(
+                        "message `{}` in {} does not have a value",
+                        key,
+                        self.path.display()
+                    ),
+                )
+            })?,
+        };
+        let variables = match variables {
+            Some(variables) => variables,
+            None => return Ok(()),
+        };
+        let unknown: Vec<_> = used
+            .iter()
+            .filter(|variable| !variables.contains(variable))
+            .map(|variable| format!("${}", variable))
+            .collect();
+        if !unknown.is_empty() {
+            let available: Vec<_> = variables.iter().map(|v| format!("${}", v)).collect();
+            return Err(Error::new(
+                span,
+                &format!(
+                    "message `{}` uses unknown variables {} (available: {})",
+                    key,
+                    unknown.join(", "),
+                    if available.is_empty() {
+                        "none".to_owned()
+                    } else {
+                        available.join(", ")
+                    }
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn missing(&self, key: &str, span: Span) -> Error {
+        Error::new(
+            span,
+            &format!("message `{}` is missing from {}", key, self.path.display()),
+        )
+    }
+
+    /// The file's absolute path, as a string literal.
+    pub(crate) fn path_literal(&self) -> Literal {
+        Literal::string(&self.path.to_string_lossy())
+    }
+
+    /// The language code of the file, which is its name without the extension, eg. `en-US`.
+    pub(crate) fn language_code(&self) -> String {
+        self.path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Makes the compiler track the FTL file, so that the crate is rebuilt when it changes.
+    pub(crate) fn track(&self) -> TokenStream {
+        let path = self.path_literal();
+        quote! {
+            const _: &str = ::std::include_str!(#path);
+        }
+    }
+}
@@ -1,6 +1,8 @@
+mod app_config;
 mod common;
 mod error;
 mod from_language;
+mod ftl;
 mod translate_enum;
 
 use error::Error;
@@ -28,12 +30,17 @@ fn wrap_fallible(
     }
 }
 
-#[proc_macro_derive(FromLanguage)]
+#[proc_macro_derive(FromLanguage, attributes(ftl))]
 pub fn derive_from_language(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     wrap_fallible(input, from_language::implementation)
 }
 
-#[proc_macro_derive(TranslateEnum, attributes(prefix))]
+#[proc_macro_derive(TranslateEnum, attributes(prefix, transparent, ftl, ftl_display, arg))]
 pub fn derive_translate_enum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     wrap_fallible(input, translate_enum::implementation)
 }
+
+#[proc_macro_derive(AppConfig, attributes(app_name, language, window, config))]
+pub fn derive_app_config(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    wrap_fallible(input, app_config::implementation)
+}
@@ -3,14 +3,20 @@ use quote::quote;
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Comma;
-use syn::{Data, DeriveInput, Fields, Lit, Meta, Type};
+use syn::{Attribute, Data, DeriveInput, Fields, Lit, Meta, Type};
 
 use crate::common::{pascal_case_to_kebab_case, snake_case_to_kebab_case};
 use crate::error::Error;
+use crate::ftl::FtlFile;
 
 pub(crate) fn implementation(ast: &DeriveInput) -> Result<TokenStream, Error> {
     let mut prefix = None;
+    let mut ftl_display = None;
     for attr in &ast.attrs {
+        if attr.path.is_ident("ftl_display") {
+            ftl_display = Some(attr);
+            continue;
+        }
         let meta = attr
             .parse_meta()
             .map_err(|e| Error::new(e.span(), &e.to_string()))?;
@@ -30,26 +36,113 @@ pub(crate) fn implementation(ast: &DeriveInput) -> Result<TokenStream, Error> {
     } else {
         return Err(Error::new(ast.span(), "enum expected"));
     };
-    Ok(implement_trait(
-        prefix.as_deref(),
-        ast.ident.clone(),
-        variants,
-    ))
+    let ftl = FtlFile::from_attributes(&ast.attrs)?;
+    let errors = match &ftl {
+        Some(ftl) => validate_keys(ftl, prefix.as_deref(), &variants),
+        None => Vec::new(),
+    };
+    let mut output = implement_trait(prefix.as_deref(), ast.ident.clone(), variants);
+    if let Some(ftl) = &ftl {
+        output.extend(ftl.track());
+    }
+    if let Some(attr) = ftl_display {
+        match &ftl {
+            Some(ftl) => output.extend(implement_display(ftl, &ast.ident)),
+            None => {
+                return Err(Error::new(
+                    attr.span(),
+                    "#[ftl_display] requires the FTL file to be specified with #[ftl(\"path\")]",
+                ))
+            }
+        }
+    }
+    for error in errors {
+        output.extend(error.emit());
+    }
+    Ok(output)
+}
+
+fn variant_key(prefix: Option<&str>, name: &Ident) -> String {
+    if let Some(prefix) = prefix {
+        format!(
+            "{}-{}",
+            prefix,
+            pascal_case_to_kebab_case(&name.to_string())
+        )
+    } else {
+        pascal_case_to_kebab_case(&name.to_string())
+    }
+}
+
+/// Checks the keys generated for each variant against the FTL file.
+///
+/// Nested variants are only checked for the presence of their message, since their attributes are
+/// defined by the inner enum. Transparent variants are skipped entirely.
+fn validate_keys(ftl: &FtlFile, prefix: Option<&str>, variants: &[Variant]) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for variant in variants {
+        let key = variant_key(prefix, &variant.name);
+        let span = variant.name.span();
+        let result = match &variant.fields {
+            None => ftl.check_pattern(&key, Some(&[]), span),
+            Some(VariantFields::Formatted(fields)) => {
+                let variables: Vec<_> = fields.iter().map(|field| field.variable.clone()).collect();
+                ftl.check_pattern(&key, Some(&variables), span)
+            }
+            Some(VariantFields::Nested(_)) => ftl.check_message(&key, span),
+            Some(VariantFields::Transparent(_)) => Ok(()),
+        };
+        errors.extend(result.err());
+    }
+    errors
 }
 
 enum VariantFields {
     Nested(Box<Type>),
-    Formatted(Vec<(Ident, Type)>),
+    Transparent(Box<Type>),
+    Formatted(Vec<Field>),
+}
+
+/// A field passed to the translation as a variable.
+struct Field {
+    /// The field's name, or `None` for the only field of a tuple variant.
+    name: Option<Ident>,
+    /// The name of the variable in the translation.
+    variable: String,
+    typ: Type,
+    /// Whether the field is a source error, marked with `#[source]` or `#[from]`. Source errors
+    /// are passed as their `Display` output, since error types can't be formatted by Fluent.
+    is_source: bool,
 }
 
 struct Variant {
     name: Ident,
     fields: Option<VariantFields>,
+    /// `#[cfg]` attributes, which have to be repeated on the variant's match arm.
+    cfgs: Vec<Attribute>,
 }
 
 fn collect_variants(ast: &Punctuated<syn::Variant, Comma>) -> Result<Vec<Variant>, Error> {
     let mut variants = Vec::new();
     for variant in ast {
+        let transparent = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("transparent"));
+        if let Some(attr) = transparent {
+            if !attr.tokens.is_empty() {
+                return Err(Error::new(
+                    attr.tokens.span(),
+                    "attribute takes no arguments",
+                ));
+            }
+            if !matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1) {
+                return Err(Error::new(
+                    attr.span(),
+                    "only variants with one unnamed field can be transparent",
+                ));
+            }
+        }
         let fields = match &variant.fields {
             Fields::Unit => None,
             Fields::Unnamed(fields) => {
@@ -59,14 +152,31 @@ fn collect_variants(ast: &Punctuated<syn::Variant, Comma>) -> Result<Vec<Variant
                         "one unnamed field expected to define attribute enum",
                     ));
                 }
-                Some(VariantFields::Nested(Box::new(
-                    fields.unnamed[0].ty.clone(),
-                )))
+                let field = &fields.unnamed[0];
+                let inner = Box::new(field.ty.clone());
+                if transparent.is_some() {
+                    Some(VariantFields::Transparent(inner))
+                } else if let Some(variable) = tuple_variable(field)? {
+                    Some(VariantFields::Formatted(vec![Field {
+                        name: None,
+                        variable,
+                        typ: field.ty.clone(),
+                        is_source: is_source(field),
+                    }]))
+                } else {
+                    Some(VariantFields::Nested(inner))
+                }
             }
             Fields::Named(fields) => {
                 let mut f = Vec::new();
                 for field in &fields.named {
-                    f.push((field.ident.as_ref().cloned().unwrap(), field.ty.clone()))
+                    let name = field.ident.as_ref().cloned().unwrap();
+                    f.push(Field {
+                        variable: snake_case_to_kebab_case(&name.to_string()),
+                        name: Some(name),
+                        typ: field.ty.clone(),
+                        is_source: is_source(field),
+                    })
                 }
                 Some(VariantFields::Formatted(f))
             }
@@ -74,29 +184,55 @@ fn collect_variants(ast: &Punctuated<syn::Variant, Comma>) -> Result<Vec<Variant
         variants.push(Variant {
             name: variant.ident.clone(),
             fields,
+            cfgs: variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("cfg"))
+                .cloned()
+                .collect(),
         })
     }
     Ok(variants)
 }
 
+fn is_source(field: &syn::Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("source") || attr.path.is_ident("from"))
+}
+
+/// Determines the variable name for the only field of a tuple variant, if it should be formatted
+/// rather than translated as a nested enum.
+///
+/// The name is given with `#[arg = "name"]`. Source errors (`#[source]` or `#[from]`) default to
+/// `error`.
+fn tuple_variable(field: &syn::Field) -> Result<Option<String>, Error> {
+    if let Some(attr) = field.attrs.iter().find(|attr| attr.path.is_ident("arg")) {
+        let meta = attr
+            .parse_meta()
+            .map_err(|e| Error::new(e.span(), &e.to_string()))?;
+        return match meta {
+            Meta::NameValue(syn::MetaNameValue {
+                lit: Lit::Str(s), ..
+            }) => Ok(Some(s.value())),
+            _ => Err(Error::new(meta.span(), "expected #[arg = \"name\"]")),
+        };
+    }
+    Ok(is_source(field).then(|| "error".to_owned()))
+}
+
 fn implement_trait(prefix: Option<&str>, typ: Ident, variants: Vec<Variant>) -> TokenStream {
     let language = Ident::new("__language", Span::call_site());
     let message = Ident::new("__message", Span::call_site());
     let mut arms = TokenStream::new();
 
-    for Variant { name, fields } in variants {
-        let variant_name = Literal::string(&if let Some(prefix) = prefix {
-            format!(
-                "{}-{}",
-                prefix,
-                pascal_case_to_kebab_case(&name.to_string())
-            )
-        } else {
-            pascal_case_to_kebab_case(&name.to_string())
-        });
+    for Variant { name, fields, cfgs } in variants {
+        let variant_name = Literal::string(&variant_key(prefix, &name));
         match fields {
             Some(VariantFields::Nested(inner)) => {
                 let arm = quote! {
+                    #(#cfgs)*
                     Self::#name(__0) => {
                         assert!(#message.is_none(), "messages may only nest once");
                         <#inner as ::mau_i18n::translate_enum::TranslateEnumAttribute>::translate_attribute(__0, #language, Some(#variant_name))
@@ -104,32 +240,68 @@ fn implement_trait(prefix: Option<&str>, typ: Ident, variants: Vec<Variant>) ->
                 };
                 arms.extend(arm);
             }
+            Some(VariantFields::Transparent(inner)) => {
+                let arm = quote! {
+                    #(#cfgs)*
+                    Self::#name(__0) => {
+                        <#inner as ::mau_i18n::translate_enum::TranslateEnumAttribute>::translate_attribute(__0, #language, #message)
+                    }
+                };
+                arms.extend(arm);
+            }
             Some(VariantFields::Formatted(fields)) => {
                 let mut fields_ts = TokenStream::new();
                 let mut with_chain = TokenStream::new();
                 let renamed_fields: Vec<_> = fields
                     .iter()
                     .enumerate()
-                    .map(|(i, (name, _))| Ident::new(&format!("__{}_{}", name, i), name.span()))
+                    .map(|(i, Field { variable, .. })| {
+                        Ident::new(
+                            &format!("__{}_{}", variable.replace('-', "_"), i),
+                            name.span(),
+                        )
+                    })
                     .collect();
-                for ((name, typ), renamed) in fields.iter().zip(&renamed_fields) {
-                    fields_ts.extend(quote! {
-                       #name: #renamed,
+                for (
+                    Field {
+                        name: field,
+                        variable,
+                        typ,
+                        is_source,
+                    },
+                    renamed,
+                ) in fields.iter().zip(&renamed_fields)
+                {
+                    fields_ts.extend(match field {
+                        Some(field) => quote! { #field: #renamed, },
+                        None => quote! { #renamed },
                     });
-                    let field_name = Literal::string(&snake_case_to_kebab_case(&name.to_string()));
-                    with_chain.extend(quote! {
-                       .with(#field_name, <#typ as ::std::clone::Clone>::clone(#renamed))
+                    let field_name = Literal::string(variable);
+                    with_chain.extend(if *is_source {
+                        quote! {
+                            .with(#field_name, ::std::string::ToString::to_string(#renamed))
+                        }
+                    } else {
+                        quote! {
+                            .with(#field_name, <#typ as ::std::clone::Clone>::clone(#renamed))
+                        }
                     });
                 }
+                let pattern = if fields.iter().any(|field| field.name.is_none()) {
+                    quote! { Self::#name(#fields_ts) }
+                } else {
+                    quote! { Self::#name { #fields_ts } }
+                };
                 let arm = quote! {
-                    Self::#name { #fields_ts } => {
+                    #(#cfgs)*
+                    #pattern => {
                         if let Some(__parent_message) = #message {
-                            Formatted::new(#language.clone(), format!(concat!("{}.", #variant_name), __parent_message))
+                            ::mau_i18n::Formatted::new(#language.clone(), format!(concat!("{}.", #variant_name), __parent_message))
                                 .format()
                                 #with_chain
                                 .done()
                         } else {
-                            Formatted::new(#language.clone(), #variant_name)
+                            ::mau_i18n::Formatted::new(#language.clone(), #variant_name)
                                 .format()
                                 #with_chain
                                 .done()
@@ -140,6 +312,7 @@ fn implement_trait(prefix: Option<&str>, typ: Ident, variants: Vec<Variant>) ->
             }
             None => {
                 let arm = quote! {
+                    #(#cfgs)*
                     Self::#name => {
                         if let Some(__parent_message) = #message {
                             #language.message(&format!(concat!("{}.", #variant_name), __parent_message))
@@ -167,3 +340,28 @@ fn implement_trait(prefix: Option<&str>, typ: Ident, variants: Vec<Variant>) ->
         }
     }
 }
+
+/// Implements `Display` with the message from the FTL file the keys were checked against, so that
+/// the file is the only place the messages are written down.
+///
+/// The file is loaded once per thread, with the language code taken from its file name.
+fn implement_display(ftl: &FtlFile, typ: &Ident) -> TokenStream {
+    let path = ftl.path_literal();
+    let language_code = Literal::string(&ftl.language_code());
+    quote! {
+        impl ::std::fmt::Display for #typ {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::thread_local! {
+                    static LANGUAGE: ::mau_i18n::Language =
+                        ::mau_i18n::Language::load(#language_code, ::std::include_str!(#path))
+                            .expect("FTL file was checked at compile time");
+                }
+                LANGUAGE.with(|language| {
+                    let message =
+                        <Self as ::mau_i18n::translate_enum::TranslateEnum>::translate(self, language);
+                    f.write_str(&::mau_i18n::strip_isolation_marks(&message))
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! FTL syntax helpers shared by `mau-i18n` and its derive macros.
+
+use std::collections::BTreeSet;
+
+use fluent_syntax::ast::{Expression, InlineExpression, Pattern, PatternElement};
+
+/// Collects the names of variables referenced by a pattern.
+pub fn pattern_variables(pattern: &Pattern<&str>) -> BTreeSet<String> {
+    let mut variables = BTreeSet::new();
+    collect_pattern(pattern, &mut variables);
+    variables
+}
+
+fn collect_pattern(pattern: &Pattern<&str>, variables: &mut BTreeSet<String>) {
+    for element in &pattern.elements {
+        if let PatternElement::Placeable { expression } = element {
+            collect_expression(expression, variables);
+        }
+    }
+}
+
+fn collect_expression(expression: &Expression<&str>, variables: &mut BTreeSet<String>) {
+    match expression {
+        Expression::Select { selector, variants } => {
+            collect_inline(selector, variables);
+            for variant in variants {
+                collect_pattern(&variant.value, variables);
+            }
+        }
+        Expression::Inline(inline) => collect_inline(inline, variables),
+    }
+}
+
+fn collect_inline(expression: &InlineExpression<&str>, variables: &mut BTreeSet<String>) {
+    match expression {
+        InlineExpression::VariableReference { id } => {
+            variables.insert(id.name.to_owned());
+        }
+        InlineExpression::FunctionReference { arguments, .. } => {
+            for argument in &arguments.positional {
+                collect_inline(argument, variables);
+            }
+            for argument in &arguments.named {
+                collect_inline(&argument.value, variables);
+            }
+        }
+        // Variables inside of terms are local to the term, but its arguments are not.
+        InlineExpression::TermReference {
+            arguments: Some(arguments),
+            ..
+        } => {
+            for argument in &arguments.positional {
+                collect_inline(argument, variables);
+            }
+            for argument in &arguments.named {
+                collect_inline(&argument.value, variables);
+            }
+        }
+        InlineExpression::Placeable { expression } => collect_expression(expression, variables),
+        _ => (),
+    }
+}
@@ -0,0 +1,116 @@
+//! Checks the coverage of a directory of FTL files against a reference language.
+//!
+//! Exits with code 1 if any problems were found, and 2 if the files could not be checked.
+//!
+//! Requires the `cli` feature, eg. `cargo run -p mau-i18n --features cli --bin mau-i18n-check`.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use mau_i18n::coverage::{self, Problem, Report};
+use serde_json::{json, Value};
+
+const USAGE: &str = "\
+usage: mau-i18n-check [options] <directory>
+
+options:
+  -r, --reference <language>  the language to check against (default: en-US)
+      --json                  print the report as JSON
+  -h, --help                  print this help message";
+
+struct Options {
+    directory: PathBuf,
+    reference: String,
+    json: bool,
+}
+
+/// Parses the command line arguments. Returns `None` if help was requested.
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut directory = None;
+    let mut reference = "en-US".to_owned();
+    let mut json = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-r" | "--reference" => {
+                reference = args
+                    .next()
+                    .ok_or_else(|| format!("{} requires a language", arg))?;
+            }
+            "--json" => json = true,
+            "-h" | "--help" => return Ok(None),
+            _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+            _ if directory.is_none() => directory = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    Ok(Some(Options {
+        directory: directory.ok_or_else(|| "no directory given".to_owned())?,
+        reference,
+        json,
+    }))
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = match coverage::check_directory(&options.directory, &options.reference) {
+        Ok(report) => report,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+    if options.json {
+        println!("{}", to_json(&report));
+    } else {
+        print!("{}", report);
+    }
+
+    if report.has_problems() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn to_json(report: &Report) -> Value {
+    let languages: Vec<_> = report
+        .languages
+        .iter()
+        .map(|language| {
+            let problems: Vec<_> = language.problems.iter().map(problem_to_json).collect();
+            json!({ "language": language.language, "problems": problems })
+        })
+        .collect();
+    json!({ "reference": report.reference, "languages": languages })
+}
+
+fn problem_to_json(problem: &Problem) -> Value {
+    match problem {
+        Problem::Syntax { line, error } => {
+            json!({ "kind": "syntax", "line": line, "error": error })
+        }
+        Problem::Missing { key } => json!({ "kind": "missing", "key": key }),
+        Problem::Extra { key } => json!({ "kind": "extra", "key": key }),
+        Problem::Empty { key } => json!({ "kind": "empty", "key": key }),
+        Problem::Variables {
+            key,
+            missing,
+            extra,
+        } => json!({ "kind": "variables", "key": key, "missing": missing, "extra": extra }),
+    }
+}
@@ -0,0 +1,337 @@
+//! Translation coverage checking.
+//!
+//! Fluent is lenient when loading translations: syntax errors are only logged, and missing
+//! messages fall back to other languages at runtime. This module compares translations against a
+//! reference language ahead of time, so that such problems can be caught before users see them.
+//!
+//! Messages are identified by keys in the same format as [`Language::message`][crate::Language]
+//! uses: `message` refers to a message's value, and `message.attribute` to one of its attributes.
+//! Terms are private to each file, so they are only checked for syntax errors.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::path::Path;
+
+use fluent_syntax::ast::{Entry, Expression, InlineExpression, Pattern, PatternElement};
+use mau_i18n_syntax::pattern_variables;
+
+use crate::Error;
+
+/// A problem found in an FTL file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The file could not be parsed. The entry containing the error is skipped by Fluent.
+    Syntax { line: usize, error: String },
+    /// A message or attribute from the reference language is not translated.
+    Missing { key: String },
+    /// A message or attribute does not exist in the reference language.
+    Extra { key: String },
+    /// A message or attribute is translated to an empty string.
+    Empty { key: String },
+    /// A translation uses different variables than the reference.
+    ///
+    /// `missing` lists variables used by the reference but not by the translation, and `extra`
+    /// lists variables used by the translation but not by the reference.
+    Variables {
+        key: String,
+        missing: Vec<String>,
+        extra: Vec<String>,
+    },
+}
+
+impl Problem {
+    fn kind_of(key: &str) -> &'static str {
+        if key.contains('.') {
+            "attribute"
+        } else {
+            "message"
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variables = |variables: &[String]| {
+            variables
+                .iter()
+                .map(|variable| format!("${}", variable))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Self::Syntax { line, error } => write!(f, "line {}: syntax error: {}", line, error),
+            Self::Missing { key } => write!(f, "missing {} `{}`", Self::kind_of(key), key),
+            Self::Extra { key } => write!(f, "extra {} `{}`", Self::kind_of(key), key),
+            Self::Empty { key } => write!(f, "empty {} `{}`", Self::kind_of(key), key),
+            Self::Variables {
+                key,
+                missing,
+                extra,
+            } => {
+                write!(
+                    f,
+                    "mismatched variables in {} `{}`",
+                    Self::kind_of(key),
+                    key
+                )?;
+                if !missing.is_empty() {
+                    write!(f, "; missing {}", variables(missing))?;
+                }
+                if !extra.is_empty() {
+                    write!(f, "; extra {}", variables(extra))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The problems found in a single language.
+#[derive(Debug, Clone)]
+pub struct LanguageReport {
+    pub language: String,
+    pub problems: Vec<Problem>,
+}
+
+/// The problems found in a set of languages.
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// The code of the reference language.
+    pub reference: String,
+    /// Reports for every checked language, including the reference.
+    pub languages: Vec<LanguageReport>,
+}
+
+impl Report {
+    /// Returns whether any of the languages has problems.
+    pub fn has_problems(&self) -> bool {
+        self.languages
+            .iter()
+            .any(|language| !language.problems.is_empty())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for language in &self.languages {
+            if language.problems.is_empty() {
+                writeln!(f, "{}: ok", language.language)?;
+            } else {
+                writeln!(
+                    f,
+                    "{}: {} problem(s)",
+                    language.language,
+                    language.problems.len()
+                )?;
+                for problem in &language.problems {
+                    writeln!(f, "  {}", problem)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed FTL file, with the variables used by each key.
+struct Entries {
+    keys: BTreeMap<String, KeyInfo>,
+    syntax_errors: Vec<Problem>,
+}
+
+struct KeyInfo {
+    variables: BTreeSet<String>,
+    is_empty: bool,
+}
+
+impl Entries {
+    fn parse(source: &str) -> Self {
+        let (resource, errors) = match fluent_syntax::parser::parse(source) {
+            Ok(resource) => (resource, Vec::new()),
+            Err((resource, errors)) => (resource, errors),
+        };
+        let syntax_errors = errors
+            .iter()
+            .map(|error| Problem::Syntax {
+                line: source[..error.pos.start].matches('\n').count() + 1,
+                error: error.to_string(),
+            })
+            .collect();
+
+        let mut keys = BTreeMap::new();
+        for entry in &resource.body {
+            if let Entry::Message(message) = entry {
+                let name = message.id.name;
+                if let Some(value) = &message.value {
+                    keys.insert(name.to_owned(), KeyInfo::new(value));
+                }
+                for attribute in &message.attributes {
+                    let key = format!("{}.{}", name, attribute.id.name);
+                    keys.insert(key, KeyInfo::new(&attribute.value));
+                }
+            }
+        }
+        Self {
+            keys,
+            syntax_errors,
+        }
+    }
+}
+
+impl KeyInfo {
+    fn new(pattern: &Pattern<&str>) -> Self {
+        Self {
+            variables: pattern_variables(pattern),
+            is_empty: is_pattern_empty(pattern),
+        }
+    }
+}
+
+/// A reference language that translations are checked against.
+pub struct Reference {
+    language: String,
+    entries: Entries,
+}
+
+impl Reference {
+    /// Parses the FTL source of the reference language.
+    pub fn new(language: &str, source: &str) -> Self {
+        Self {
+            language: language.to_owned(),
+            entries: Entries::parse(source),
+        }
+    }
+
+    /// Returns the problems found in the reference language itself, that is syntax errors and
+    /// empty messages.
+    pub fn problems(&self) -> LanguageReport {
+        let mut problems = self.entries.syntax_errors.clone();
+        problems.extend(
+            self.entries
+                .keys
+                .iter()
+                .filter(|(_, info)| info.is_empty)
+                .map(|(key, _)| Problem::Empty { key: key.clone() }),
+        );
+        LanguageReport {
+            language: self.language.clone(),
+            problems,
+        }
+    }
+
+    /// Checks a translation against the reference.
+    ///
+    /// `parent` is the FTL source of the language the translation falls back to before falling
+    /// back to the reference, eg. `de` for `de-AT`. Keys missing from the translation that are
+    /// present in the parent are not reported, since the parent's translation will be used.
+    pub fn check(&self, language: &str, source: &str, parent: Option<&str>) -> LanguageReport {
+        let translation = Entries::parse(source);
+        let parent = parent.map(Entries::parse);
+        let mut problems = translation.syntax_errors.clone();
+
+        for (key, reference) in &self.entries.keys {
+            let info = match translation.keys.get(key) {
+                Some(info) => info,
+                None => {
+                    let in_parent = parent
+                        .as_ref()
+                        .is_some_and(|parent| parent.keys.contains_key(key));
+                    if !in_parent {
+                        problems.push(Problem::Missing { key: key.clone() });
+                    }
+                    continue;
+                }
+            };
+            if info.is_empty && !reference.is_empty {
+                problems.push(Problem::Empty { key: key.clone() });
+            }
+            if info.variables != reference.variables {
+                problems.push(Problem::Variables {
+                    key: key.clone(),
+                    missing: reference
+                        .variables
+                        .difference(&info.variables)
+                        .cloned()
+                        .collect(),
+                    extra: info
+                        .variables
+                        .difference(&reference.variables)
+                        .cloned()
+                        .collect(),
+                });
+            }
+        }
+        problems.extend(
+            translation
+                .keys
+                .keys()
+                .filter(|key| !self.entries.keys.contains_key(*key))
+                .map(|key| Problem::Extra { key: key.clone() }),
+        );
+
+        LanguageReport {
+            language: language.to_owned(),
+            problems,
+        }
+    }
+}
+
+/// Checks all FTL files in a directory against the reference language.
+///
+/// The directory is expected to contain files named after their language codes, like
+/// `en-US.ftl` and `de.ftl`. Regional languages such as `de-AT` are only required to translate
+/// keys missing from their parent language, if the parent's file is present.
+pub fn check_directory(directory: &Path, reference: &str) -> Result<Report, Error> {
+    let io_error = |path: &Path| {
+        let path = path.to_owned();
+        move |error| Error::Io { path, error }
+    };
+
+    let mut sources = BTreeMap::new();
+    for entry in std::fs::read_dir(directory).map_err(io_error(directory))? {
+        let path = entry.map_err(io_error(directory))?.path();
+        if path.extension().is_none_or(|extension| extension != "ftl") {
+            continue;
+        }
+        let language = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(language) => language.to_owned(),
+            None => continue,
+        };
+        let source = std::fs::read_to_string(&path).map_err(io_error(&path))?;
+        sources.insert(language, source);
+    }
+
+    let reference_source = sources
+        .get(reference)
+        .ok_or_else(|| Error::MissingReference {
+            path: directory.join(format!("{}.ftl", reference)),
+        })?;
+    let reference = Reference::new(reference, reference_source);
+
+    let mut languages = vec![reference.problems()];
+    for (language, source) in &sources {
+        if *language == reference.language {
+            continue;
+        }
+        let parent = language
+            .split_once(['-', '_'])
+            .and_then(|(parent, _)| sources.get(parent))
+            .map(String::as_str);
+        languages.push(reference.check(language, source, parent));
+    }
+
+    Ok(Report {
+        reference: reference.language,
+        languages,
+    })
+}
+
+/// Returns whether a pattern formats to whitespace only.
+fn is_pattern_empty(pattern: &Pattern<&str>) -> bool {
+    pattern.elements.iter().all(|element| match element {
+        PatternElement::TextElement { value } => value.trim().is_empty(),
+        PatternElement::Placeable {
+            expression: Expression::Inline(InlineExpression::StringLiteral { value }),
+        } => value.trim().is_empty(),
+        PatternElement::Placeable { .. } => false,
+    })
+}
@@ -1,16 +1,28 @@
 //! Error types.
 
 use std::fmt::Display;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum Error {
     InvalidLanguageCode,
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    MissingReference {
+        path: PathBuf,
+    },
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::InvalidLanguageCode => write!(f, "invalid language code"),
+            Self::Io { path, error } => write!(f, "cannot read {}: {}", path.display(), error),
+            Self::MissingReference { path } => {
+                write!(f, "reference language file {} not found", path.display())
+            }
         }
     }
 }
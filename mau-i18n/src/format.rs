@@ -1,10 +1,13 @@
 //! Formatted messages.
 
 use std::borrow::Cow;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
+use chrono::{DateTime, FixedOffset, Local, TimeZone};
 use fluent::{FluentArgs, FluentValue};
 
-use crate::Language;
+use crate::{FluentDateTime, Language};
 
 /// A formatted message.
 pub struct Formatted {
@@ -52,26 +55,11 @@ impl<'f> Formatter<'f> {
     }
 
     /// Finishes formatting the string.
+    ///
+    /// Like with [`Language::message`], the key may refer to an attribute of a message, and missing
+    /// messages are looked up in the language's fallback chain.
     pub fn done(self) -> String {
-        let mut errors = Vec::new();
-        let message = match self.language.bundle.get_message(self.key) {
-            Some(message) => message,
-            None => {
-                log::error!("message {:?} is missing", self.key);
-                return self.key.to_owned();
-            }
-        };
-        let pattern = match message.value() {
-            Some(value) => value,
-            None => {
-                log::error!("message {:?} doesn't have a value", self.key);
-                return self.key.to_owned();
-            }
-        };
-        self.language
-            .bundle
-            .format_pattern(pattern, Some(&self.args), &mut errors)
-            .into_owned()
+        self.language.format(self.key, Some(&self.args))
     }
 }
 
@@ -81,6 +69,7 @@ pub enum FormatArg<'a> {
     Unsigned(u64),
     Float(f64),
     String(Cow<'a, str>),
+    DateTime(DateTime<FixedOffset>),
 }
 
 macro_rules! format_arg_from {
@@ -120,6 +109,29 @@ impl From<String> for FormatArg<'_> {
     }
 }
 
+/// Paths are displayed lossily, replacing invalid Unicode with U+FFFD.
+impl From<PathBuf> for FormatArg<'_> {
+    fn from(path: PathBuf) -> Self {
+        path.display().to_string().into()
+    }
+}
+
+impl<Tz> From<DateTime<Tz>> for FormatArg<'_>
+where
+    Tz: TimeZone,
+{
+    fn from(datetime: DateTime<Tz>) -> Self {
+        Self::DateTime(datetime.fixed_offset())
+    }
+}
+
+/// System times are displayed in the local time zone.
+impl From<SystemTime> for FormatArg<'_> {
+    fn from(time: SystemTime) -> Self {
+        DateTime::<Local>::from(time).into()
+    }
+}
+
 impl<'a> From<FormatArg<'a>> for FluentValue<'a> {
     fn from(arg: FormatArg<'a>) -> Self {
         match arg {
@@ -127,6 +139,9 @@ impl<'a> From<FormatArg<'a>> for FluentValue<'a> {
             FormatArg::Unsigned(x) => x.into(),
             FormatArg::Float(x) => x.into(),
             FormatArg::String(s) => s.into(),
+            FormatArg::DateTime(datetime) => {
+                FluentValue::Custom(Box::new(FluentDateTime::new(datetime)))
+            }
         }
     }
 }
@@ -0,0 +1,203 @@
+//! Fluent functions available to translations.
+//!
+//! Every bundle gets the built-in `NUMBER` and `DATETIME` functions, which format values according
+//! to the bundle's [`Locale`]. Numbers passed to messages directly are formatted the same way as
+//! `NUMBER` with no options. Apps can register their own functions through [`Functions`].
+
+use std::borrow::Cow;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset, Local, TimeZone};
+use fluent::types::{FluentNumber, FluentType};
+use fluent::{FluentArgs, FluentBundle, FluentResource, FluentValue};
+use intl_memoizer::IntlLangMemoizer;
+
+use crate::locale::{DateTimeOptions, Locale};
+
+/// A date and time that can be passed to translations, and formatted using `DATETIME`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FluentDateTime {
+    pub datetime: DateTime<FixedOffset>,
+    pub options: DateTimeOptions,
+}
+
+impl FluentDateTime {
+    /// Creates a new date and time value with default formatting options.
+    pub fn new(datetime: DateTime<FixedOffset>) -> Self {
+        Self {
+            datetime,
+            options: DateTimeOptions::default(),
+        }
+    }
+}
+
+impl FluentType for FluentDateTime {
+    fn duplicate(&self) -> Box<dyn FluentType + Send> {
+        Box::new(self.clone())
+    }
+
+    fn as_string(&self, intls: &IntlLangMemoizer) -> Cow<'static, str> {
+        intls
+            .with_try_get::<Locale, _, _>((), |locale| {
+                locale.format_datetime(&self.datetime, &self.options)
+            })
+            .unwrap_or_else(|never| match never {})
+            .into()
+    }
+
+    fn as_string_threadsafe(
+        &self,
+        intls: &intl_memoizer::concurrent::IntlLangMemoizer,
+    ) -> Cow<'static, str> {
+        intls
+            .with_try_get::<Locale, _, _>((), |locale| {
+                locale.format_datetime(&self.datetime, &self.options)
+            })
+            .unwrap_or_else(|never| match never {})
+            .into()
+    }
+}
+
+/// A function callable from translations.
+///
+/// Functions receive the locale of the bundle they're called from, the positional arguments, and
+/// the named arguments.
+pub type Function =
+    dyn for<'a> Fn(&Locale, &[FluentValue<'a>], &FluentArgs) -> FluentValue<'a> + Send + Sync;
+
+/// A set of functions to register on every bundle of a language.
+///
+/// ```
+/// use mau_i18n::fluent::FluentValue;
+/// use mau_i18n::Functions;
+///
+/// let functions = Functions::new().with("FILESIZE", |locale, positional, _named| {
+///     match positional.first() {
+///         Some(FluentValue::Number(bytes)) => {
+///             let mut megabytes = bytes.clone();
+///             megabytes.value /= 1_000_000.0;
+///             megabytes.options.maximum_fraction_digits = Some(1);
+///             format!("{} MB", locale.format_number(&megabytes)).into()
+///         }
+///         _ => FluentValue::Error,
+///     }
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct Functions {
+    functions: Vec<(String, Arc<Function>)>,
+}
+
+impl Functions {
+    /// Creates a set containing no functions other than the built-in ones.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a function to the set.
+    ///
+    /// By convention, function names are in upper case.
+    pub fn add<F>(&mut self, name: impl Into<String>, function: F)
+    where
+        F: for<'a> Fn(&Locale, &[FluentValue<'a>], &FluentArgs) -> FluentValue<'a>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.functions.push((name.into(), Arc::new(function)));
+    }
+
+    /// Adds a function to the set, and returns the set.
+    pub fn with<F>(mut self, name: impl Into<String>, function: F) -> Self
+    where
+        F: for<'a> Fn(&Locale, &[FluentValue<'a>], &FluentArgs) -> FluentValue<'a>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.add(name, function);
+        self
+    }
+
+    /// Registers the built-in functions and the functions from this set on a bundle.
+    pub(crate) fn register(&self, bundle: &mut FluentBundle<FluentResource>) {
+        bundle.set_formatter(Some(format_value));
+        let locale = Locale::new(bundle.locales[0].clone());
+        let builtins: [(&str, Arc<Function>); 2] = [
+            ("NUMBER", Arc::new(number)),
+            ("DATETIME", Arc::new(datetime)),
+        ];
+        let functions = builtins.into_iter().chain(
+            self.functions
+                .iter()
+                .map(|(name, function)| (name.as_str(), Arc::clone(function))),
+        );
+        for (name, function) in functions {
+            let locale = locale.clone();
+            let result = bundle.add_function(name, move |positional, named| {
+                function(&locale, positional, named)
+            });
+            if let Err(error) = result {
+                log::error!("cannot register function {}: {}", name, error);
+            }
+        }
+    }
+}
+
+/// Formats numbers according to the bundle's locale. Other values are formatted by Fluent.
+fn format_value(value: &FluentValue, intls: &IntlLangMemoizer) -> Option<String> {
+    match value {
+        FluentValue::Number(number) => Some(
+            intls
+                .with_try_get::<Locale, _, _>((), |locale| locale.format_number(number))
+                .unwrap_or_else(|never| match never {}),
+        ),
+        _ => None,
+    }
+}
+
+/// `NUMBER($value, ...options)`: sets number formatting options.
+fn number<'a>(
+    _locale: &Locale,
+    positional: &[FluentValue<'a>],
+    named: &FluentArgs,
+) -> FluentValue<'a> {
+    let mut number = match positional.first() {
+        Some(FluentValue::Number(number)) => number.clone(),
+        Some(FluentValue::String(string)) => match FluentNumber::from_str(string) {
+            Ok(number) => number,
+            Err(_) => return FluentValue::Error,
+        },
+        _ => return FluentValue::Error,
+    };
+    number.options.merge(named);
+    FluentValue::Number(number)
+}
+
+/// `DATETIME($value, dateStyle: ..., timeStyle: ...)`: sets date formatting options.
+///
+/// Numbers are interpreted as Unix timestamps in seconds, and displayed in local time.
+fn datetime<'a>(
+    _locale: &Locale,
+    positional: &[FluentValue<'a>],
+    named: &FluentArgs,
+) -> FluentValue<'a> {
+    let mut datetime = match positional.first() {
+        Some(FluentValue::Custom(custom)) => {
+            match custom.as_any().downcast_ref::<FluentDateTime>() {
+                Some(datetime) => datetime.clone(),
+                None => return FluentValue::Error,
+            }
+        }
+        Some(FluentValue::Number(number)) => {
+            match Local.timestamp_opt(number.value as i64, 0).single() {
+                Some(datetime) => FluentDateTime::new(datetime.fixed_offset()),
+                None => return FluentValue::Error,
+            }
+        }
+        _ => return FluentValue::Error,
+    };
+    datetime.options.merge(named);
+    FluentValue::Custom(Box::new(datetime))
+}
@@ -3,43 +3,115 @@
 use std::fmt::Display;
 use std::rc::Rc;
 
-use fluent::{FluentBundle, FluentMessage, FluentResource};
+use fluent::{FluentArgs, FluentBundle, FluentResource};
 use fluent_syntax::ast::Pattern;
-use unic_langid::LanguageIdentifier;
+use unic_langid::{CharacterDirection, LanguageIdentifier};
 
-use crate::Error;
+use crate::{Error, Functions};
 
+type Bundle = FluentBundle<FluentResource>;
+
+/// The direction text in a language is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// Removes the Unicode isolation marks that translations wrap placeables in.
+///
+/// The marks are only understood by the app's renderer, so text that's passed somewhere else, like
+/// a log file or a native dialog, should have them removed first.
+pub fn strip_isolation_marks(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !matches!(c, '\u{2068}' | '\u{2069}'))
+        .collect()
+}
+
+/// A loaded language.
+///
+/// A language consists of a chain of bundles, for example `de-AT`, `de`, `en-US`. Messages and
+/// attributes missing from a bundle are looked up in the next one, so that partially translated
+/// languages fall back to a more complete one instead of displaying raw keys.
 #[derive(Clone)]
 pub struct Language {
-    pub(crate) bundle: Rc<FluentBundle<FluentResource>>,
+    pub(crate) bundles: Vec<Rc<Bundle>>,
 }
 
 impl Language {
     /// Loads a language with the given locale code, from the provided FTL source.
     pub fn load(language_code: &str, ftl_source: &str) -> Result<Self, Error> {
+        Self::load_resources(language_code, &[ftl_source])
+    }
+
+    /// Loads a language with the given locale code, from multiple FTL sources. Messages from
+    /// later sources replace messages with the same key from earlier sources.
+    pub fn load_resources(language_code: &str, ftl_sources: &[&str]) -> Result<Self, Error> {
+        Self::load_with_functions(language_code, ftl_sources, &Functions::new())
+    }
+
+    /// Like [`load_resources`][Self::load_resources], but also registers the given functions,
+    /// which can then be called from translations.
+    pub fn load_with_functions(
+        language_code: &str,
+        ftl_sources: &[&str],
+        functions: &Functions,
+    ) -> Result<Self, Error> {
         log::info!("loading language {}", language_code);
 
         let identifier: LanguageIdentifier = language_code
             .parse()
             .map_err(|_| Error::InvalidLanguageCode)?;
         let mut bundle = FluentBundle::new(vec![identifier]);
-        bundle.set_use_isolating(false); // TODO: BiDi?
-        let resource = match FluentResource::try_new(ftl_source.to_owned()) {
-            Ok(resource) => resource,
-            Err((resource, errors)) => {
-                Self::log_errors(language_code, &errors);
-                resource
-            }
-        };
-        if let Err(errors) = bundle.add_resource(resource) {
-            Self::log_errors(language_code, &errors);
+        // Placeables are wrapped in Unicode isolation marks, so that eg. an English file name
+        // doesn't scramble the surrounding Arabic sentence. Plain-text sinks strip them using
+        // `strip_isolation_marks`.
+        bundle.set_use_isolating(true);
+        functions.register(&mut bundle);
+        for &ftl_source in ftl_sources {
+            let resource = match FluentResource::try_new(ftl_source.to_owned()) {
+                Ok(resource) => resource,
+                Err((resource, errors)) => {
+                    Self::log_errors(language_code, &errors);
+                    resource
+                }
+            };
+            bundle.add_resource_overriding(resource);
         }
 
         Ok(Self {
-            bundle: Rc::new(bundle),
+            bundles: vec![Rc::new(bundle)],
         })
     }
 
+    /// Appends the bundles of another language to the end of this language's fallback chain.
+    ///
+    /// Messages missing from this language will be looked up in `fallback`.
+    pub fn with_fallback(mut self, fallback: Language) -> Self {
+        self.bundles.extend(fallback.bundles);
+        self
+    }
+
+    /// Returns the locale codes of the bundles in the fallback chain, in lookup order.
+    pub fn fallback_chain(&self) -> impl Iterator<Item = &LanguageIdentifier> {
+        self.bundles
+            .iter()
+            .flat_map(|bundle| bundle.locales.first())
+    }
+
+    /// Returns the direction the language is written in, based on the first language in the
+    /// fallback chain.
+    pub fn direction(&self) -> Direction {
+        let identifier = self
+            .bundles
+            .first()
+            .and_then(|bundle| bundle.locales.first());
+        match identifier.map(LanguageIdentifier::character_direction) {
+            Some(CharacterDirection::RTL) => Direction::RightToLeft,
+            _ => Direction::LeftToRight,
+        }
+    }
+
     fn log_errors<T>(language_code: &str, errors: &[T])
     where
         T: Display,
@@ -52,59 +124,63 @@ impl Language {
         }
     }
 
-    fn get_message(&self, key: &str) -> Option<FluentMessage> {
-        Some(match self.bundle.get_message(key) {
-            Some(message) => message,
-            None => {
-                log::error!("message {:?} is missing", key);
-                return None;
-            }
-        })
+    /// Looks up a message or its attribute in a single bundle.
+    fn find_pattern<'b>(
+        bundle: &'b Bundle,
+        message_name: &str,
+        attribute_name: Option<&str>,
+    ) -> Option<&'b Pattern<&'b str>> {
+        let message = bundle.get_message(message_name)?;
+        match attribute_name {
+            Some(attribute_name) => Some(message.get_attribute(attribute_name)?.value()),
+            None => message.value(),
+        }
     }
 
-    /// Resolves the key to a pattern.
+    /// Resolves the key to a pattern, along with the bundle it was found in.
     ///
     /// Note that the key can contain a dot, in which case an attribute will be looked up from the
     /// message under the given key. For instance, `example.hello` will look up attribute `hello`
     /// from message `example`. An attribute can only appear once.
-    pub(crate) fn get_pattern(&self, key: &str) -> Option<&Pattern<&str>> {
-        if let Some(dot_index) = key.find('.') {
-            let message_name = &key[..dot_index];
-            let message = self.get_message(message_name)?;
-            let attribute_name = &key[(dot_index + 1)..];
-            let attribute = match message.get_attribute(attribute_name) {
-                Some(attribute) => attribute,
-                None => {
-                    log::error!(
-                        "message {:?} does not have the attribute {:?}",
-                        &key[dot_index..],
-                        attribute_name
+    ///
+    /// Bundles are searched in fallback chain order, for each message and attribute separately.
+    pub(crate) fn get_pattern(&self, key: &str) -> Option<(&Bundle, &Pattern<&str>)> {
+        let (message_name, attribute_name) = match key.split_once('.') {
+            Some((message_name, attribute_name)) => (message_name, Some(attribute_name)),
+            None => (key, None),
+        };
+        for (i, bundle) in self.bundles.iter().enumerate() {
+            if let Some(pattern) = Self::find_pattern(bundle, message_name, attribute_name) {
+                if i > 0 {
+                    log::debug!(
+                        "message {:?} is missing from {}, using the one from {}",
+                        key,
+                        self.bundles[0].locales[0],
+                        bundle.locales[0]
                     );
-                    return None;
                 }
-            };
-            Some(attribute.value())
-        } else {
-            let message = self.get_message(key)?;
-            Some(match message.value() {
-                Some(value) => value,
-                None => {
-                    log::error!("message {:?} doesn't have a value", key);
-                    return None;
-                }
-            })
+                return Some((bundle, pattern));
+            }
         }
+        log::error!("message {:?} is missing", key);
+        None
     }
 
-    /// Returns a non-parametric message.
-    pub fn message(&self, key: &str) -> String {
+    /// Formats the message under the given key, or returns the key itself if it is missing from
+    /// all bundles.
+    pub(crate) fn format(&self, key: &str, args: Option<&FluentArgs>) -> String {
         let mut errors = Vec::new();
-        let pattern = match self.get_pattern(key) {
-            Some(pattern) => pattern,
+        let (bundle, pattern) = match self.get_pattern(key) {
+            Some(found) => found,
             None => return key.to_owned(),
         };
-        self.bundle
-            .format_pattern(pattern, None, &mut errors)
+        bundle
+            .format_pattern(pattern, args, &mut errors)
             .into_owned()
     }
+
+    /// Returns a non-parametric message.
+    pub fn message(&self, key: &str) -> String {
+        self.format(key, None)
+    }
 }
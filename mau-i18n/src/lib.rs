@@ -1,19 +1,26 @@
 extern crate self as mau_i18n;
 
+pub mod coverage;
 mod error;
 mod format;
 pub mod from_language;
+mod functions;
 mod language;
+mod locale;
 mod map;
 pub mod translate_enum;
 
 pub use error::*;
-pub use format::Formatted;
+pub use format::{FormatArg, Formatted, Formatter};
+pub use functions::{FluentDateTime, Function, Functions};
 pub use language::*;
+pub use locale::{DateTimeOptions, DateTimeStyle, Locale};
 pub use map::Map;
 
 pub use mau_i18n_macros::{FromLanguage, TranslateEnum};
 
+pub use chrono;
+pub use fluent;
 pub use unic_langid;
 
 #[allow(dead_code)]
@@ -31,5 +38,6 @@ fn _expand_translate_enum() {
         MultipleWordsHelloWorld,
         WithFields { a_field: String },
         WithNested(Nested),
+        WithArgument(#[arg = "argument"] String),
     }
 }
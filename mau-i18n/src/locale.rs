@@ -0,0 +1,754 @@
+//! Locale-specific formatting of numbers and dates.
+
+use std::convert::Infallible;
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike};
+use fluent::types::{FluentNumber, FluentNumberStyle};
+use intl_memoizer::Memoizable;
+use unic_langid::LanguageIdentifier;
+
+/// The order of the day, month, and year in numeric dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// How dates with spelled out month names are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LongDate {
+    /// `January 5, 2024`
+    MonthFirst,
+    /// `5 janvier 2024`
+    DayFirst,
+    /// `5. Januar 2024`
+    DayWithDot,
+    /// `5 de enero de 2024`
+    DayWithDe,
+}
+
+/// The length of a formatted date or time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateTimeStyle {
+    Full,
+    Long,
+    Medium,
+    Short,
+}
+
+impl DateTimeStyle {
+    fn parse(style: &str) -> Option<Self> {
+        Some(match style {
+            "full" => Self::Full,
+            "long" => Self::Long,
+            "medium" => Self::Medium,
+            "short" => Self::Short,
+            _ => return None,
+        })
+    }
+}
+
+/// Options for formatting dates and times, as accepted by the `DATETIME` function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DateTimeOptions {
+    /// The style of the date part. Set from the `dateStyle` option.
+    pub date_style: Option<DateTimeStyle>,
+    /// The style of the time part. Set from the `timeStyle` option.
+    pub time_style: Option<DateTimeStyle>,
+}
+
+impl DateTimeOptions {
+    /// Overrides options with the named arguments passed to `DATETIME`.
+    pub fn merge(&mut self, options: &fluent::FluentArgs) {
+        for (key, value) in options.iter() {
+            let style = match value {
+                fluent::FluentValue::String(style) => DateTimeStyle::parse(style),
+                _ => None,
+            };
+            match key {
+                "dateStyle" => self.date_style = style,
+                "timeStyle" => self.time_style = style,
+                _ => (),
+            }
+        }
+    }
+}
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const MONTHS_DE: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const MONTHS_FR: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const MONTHS_ES: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+const MONTHS_IT: [&str; 12] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+const MONTHS_PT: [&str; 12] = [
+    "janeiro",
+    "fevereiro",
+    "março",
+    "abril",
+    "maio",
+    "junho",
+    "julho",
+    "agosto",
+    "setembro",
+    "outubro",
+    "novembro",
+    "dezembro",
+];
+const MONTHS_NL: [&str; 12] = [
+    "januari",
+    "februari",
+    "maart",
+    "april",
+    "mei",
+    "juni",
+    "juli",
+    "augustus",
+    "september",
+    "oktober",
+    "november",
+    "december",
+];
+
+const WEEKDAYS_EN: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+const WEEKDAYS_DE: [&str; 7] = [
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+    "Sonntag",
+];
+const WEEKDAYS_FR: [&str; 7] = [
+    "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+];
+const WEEKDAYS_ES: [&str; 7] = [
+    "lunes",
+    "martes",
+    "miércoles",
+    "jueves",
+    "viernes",
+    "sábado",
+    "domingo",
+];
+const WEEKDAYS_IT: [&str; 7] = [
+    "lunedì",
+    "martedì",
+    "mercoledì",
+    "giovedì",
+    "venerdì",
+    "sabato",
+    "domenica",
+];
+const WEEKDAYS_PT: [&str; 7] = [
+    "segunda-feira",
+    "terça-feira",
+    "quarta-feira",
+    "quinta-feira",
+    "sexta-feira",
+    "sábado",
+    "domingo",
+];
+const WEEKDAYS_NL: [&str; 7] = [
+    "maandag",
+    "dinsdag",
+    "woensdag",
+    "donderdag",
+    "vrijdag",
+    "zaterdag",
+    "zondag",
+];
+
+/// Formatting conventions of a locale.
+///
+/// Locales are resolved from a small built-in table keyed by language and region. Unknown
+/// languages use ISO 8601 dates and a 24-hour clock.
+///
+/// The table is maintained by hand rather than generated from CLDR, to keep its size down, and
+/// only covers common conventions. In particular, month and weekday names are only known for
+/// English, German, French, Spanish, Italian, Portuguese, and Dutch. Other languages format the
+/// long and full date styles as numeric dates, like the medium style. Apps that need exact
+/// formatting for other locales can register their own functions through
+/// [`Functions`][crate::Functions].
+#[derive(Debug, Clone)]
+pub struct Locale {
+    identifier: LanguageIdentifier,
+    decimal_separator: &'static str,
+    group_separator: &'static str,
+    percent_sign: &'static str,
+    date_order: DateOrder,
+    date_separator: &'static str,
+    /// Whether numeric dates use two-digit years in the short style.
+    short_year: bool,
+    long_date: LongDate,
+    month_names: Option<&'static [&'static str; 12]>,
+    /// Weekday names starting with Monday, used in the full date style.
+    weekday_names: Option<&'static [&'static str; 7]>,
+    /// The separator between the weekday and the rest of a full date.
+    weekday_separator: &'static str,
+    /// The AM and PM markers, if the locale uses a 12-hour clock.
+    day_periods: Option<(&'static str, &'static str)>,
+    date_time_separator: &'static str,
+}
+
+impl Locale {
+    /// Resolves the formatting conventions for the given language identifier.
+    pub fn new(identifier: LanguageIdentifier) -> Self {
+        let region = identifier.region.as_ref().map(|region| region.as_str());
+        let mut locale = Self {
+            identifier: identifier.clone(),
+            decimal_separator: ".",
+            group_separator: ",",
+            percent_sign: "%",
+            date_order: DateOrder::YearMonthDay,
+            date_separator: "-",
+            short_year: false,
+            long_date: LongDate::DayFirst,
+            month_names: None,
+            weekday_names: None,
+            weekday_separator: ", ",
+            day_periods: None,
+            date_time_separator: " ",
+        };
+        match identifier.language.as_str() {
+            "en" => {
+                locale.month_names = Some(&MONTHS_EN);
+                locale.weekday_names = Some(&WEEKDAYS_EN);
+                locale.date_time_separator = ", ";
+                match region {
+                    Some("GB" | "IE") => {
+                        locale.date_order = DateOrder::DayMonthYear;
+                        locale.date_separator = "/";
+                    }
+                    Some("AU" | "NZ" | "IN") => {
+                        locale.date_order = DateOrder::DayMonthYear;
+                        locale.date_separator = "/";
+                        locale.day_periods = Some(("am", "pm"));
+                    }
+                    Some("CA") => {
+                        locale.long_date = LongDate::MonthFirst;
+                        locale.day_periods = Some(("a.m.", "p.m."));
+                    }
+                    _ => {
+                        locale.date_order = DateOrder::MonthDayYear;
+                        locale.date_separator = "/";
+                        locale.short_year = true;
+                        locale.long_date = LongDate::MonthFirst;
+                        locale.day_periods = Some(("AM", "PM"));
+                    }
+                }
+            }
+            "de" => {
+                locale.set_day_month_year(",", ".", ".");
+                locale.percent_sign = "\u{a0}%";
+                locale.short_year = true;
+                locale.long_date = LongDate::DayWithDot;
+                locale.month_names = Some(&MONTHS_DE);
+                locale.weekday_names = Some(&WEEKDAYS_DE);
+                if region == Some("CH") {
+                    locale.decimal_separator = ".";
+                    locale.group_separator = "’";
+                }
+            }
+            "fr" => {
+                locale.set_day_month_year(",", "\u{202f}", "/");
+                locale.percent_sign = "\u{a0}%";
+                locale.month_names = Some(&MONTHS_FR);
+                locale.weekday_names = Some(&WEEKDAYS_FR);
+                locale.weekday_separator = " ";
+                if region == Some("CA") {
+                    locale.date_order = DateOrder::YearMonthDay;
+                    locale.date_separator = "-";
+                }
+            }
+            "es" => {
+                locale.set_day_month_year(",", ".", "/");
+                locale.percent_sign = "\u{a0}%";
+                locale.long_date = LongDate::DayWithDe;
+                locale.month_names = Some(&MONTHS_ES);
+                locale.weekday_names = Some(&WEEKDAYS_ES);
+                if region == Some("MX") {
+                    locale.decimal_separator = ".";
+                    locale.group_separator = ",";
+                }
+            }
+            "it" => {
+                locale.set_day_month_year(",", ".", "/");
+                locale.month_names = Some(&MONTHS_IT);
+                locale.weekday_names = Some(&WEEKDAYS_IT);
+                locale.weekday_separator = " ";
+            }
+            "pt" => {
+                locale.set_day_month_year(",", ".", "/");
+                locale.long_date = LongDate::DayWithDe;
+                locale.month_names = Some(&MONTHS_PT);
+                locale.weekday_names = Some(&WEEKDAYS_PT);
+            }
+            "nl" => {
+                locale.set_day_month_year(",", ".", "-");
+                locale.month_names = Some(&MONTHS_NL);
+                locale.weekday_names = Some(&WEEKDAYS_NL);
+                locale.weekday_separator = " ";
+            }
+            "da" | "tr" => locale.set_day_month_year(",", ".", "."),
+            "cs" | "sk" | "pl" | "ru" | "uk" | "bg" | "nb" | "no" | "fi" => {
+                locale.set_day_month_year(",", "\u{a0}", ".");
+                locale.percent_sign = "\u{a0}%";
+            }
+            "sv" => {
+                locale.decimal_separator = ",";
+                locale.group_separator = "\u{a0}";
+                locale.percent_sign = "\u{a0}%";
+            }
+            "ja" | "zh" => locale.date_separator = "/",
+            "ko" => {
+                locale.date_separator = ". ";
+                locale.day_periods = Some(("AM", "PM"));
+            }
+            "he" => locale.set_day_month_year(".", ",", "."),
+            "ar" => {
+                locale.set_day_month_year(".", ",", "/");
+                locale.day_periods = Some(("ص", "م"));
+            }
+            _ => (),
+        }
+        locale
+    }
+
+    fn set_day_month_year(
+        &mut self,
+        decimal_separator: &'static str,
+        group_separator: &'static str,
+        date_separator: &'static str,
+    ) {
+        self.decimal_separator = decimal_separator;
+        self.group_separator = group_separator;
+        self.date_order = DateOrder::DayMonthYear;
+        self.date_separator = date_separator;
+    }
+
+    /// Returns the identifier of the locale.
+    pub fn identifier(&self) -> &LanguageIdentifier {
+        &self.identifier
+    }
+
+    /// Formats a number according to the locale's conventions and the number's options.
+    ///
+    /// Supported options are `style` (`decimal`, `percent`, or `currency` together with
+    /// `currency`), `useGrouping`, `minimumIntegerDigits`, `minimumFractionDigits`,
+    /// `maximumFractionDigits`, and `maximumSignificantDigits`.
+    pub fn format_number(&self, number: &FluentNumber) -> String {
+        let options = &number.options;
+        let mut value = number.value;
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        if options.style == FluentNumberStyle::Percent {
+            value *= 100.0;
+        }
+        if let Some(digits) = options.maximum_significant_digits {
+            if digits > 0 && value != 0.0 {
+                let magnitude = value.abs().log10().floor() as i32;
+                let factor = 10f64.powi(digits as i32 - 1 - magnitude);
+                value = (value * factor).round() / factor;
+            }
+        }
+
+        let is_currency =
+            options.style == FluentNumberStyle::Currency && options.currency.is_some();
+        let (minimum_fraction_digits, maximum_fraction_digits) = match (
+            options.minimum_fraction_digits,
+            options.maximum_fraction_digits,
+        ) {
+            (None, None) if is_currency => (2, Some(2)),
+            (None, None) if options.style == FluentNumberStyle::Percent => (0, Some(0)),
+            // Significant digits take precedence over fraction digits.
+            (None, None) if options.maximum_significant_digits.is_some() => (0, None),
+            // Like `Intl.NumberFormat`, show at most 3 fraction digits by default.
+            (minimum, maximum) => (minimum.unwrap_or(0), Some(maximum.unwrap_or(3))),
+        };
+        let digits = match maximum_fraction_digits {
+            Some(maximum) => {
+                let maximum = maximum.max(minimum_fraction_digits);
+                // Round half away from zero, rather than to even like the formatting machinery.
+                let factor = 10f64.powi(maximum as i32);
+                format!("{:.*}", maximum, (value.abs() * factor).round() / factor)
+            }
+            None => value.abs().to_string(),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut fraction = fraction.to_owned();
+        while fraction.len() > minimum_fraction_digits && fraction.ends_with('0') {
+            fraction.pop();
+        }
+        while fraction.len() < minimum_fraction_digits {
+            fraction.push('0');
+        }
+        let mut integer = integer.to_owned();
+        while integer.len() < options.minimum_integer_digits.unwrap_or(0) {
+            integer.insert(0, '0');
+        }
+
+        let mut formatted = String::with_capacity(integer.len() * 2);
+        let is_zero = integer.bytes().chain(fraction.bytes()).all(|b| b == b'0');
+        if value < 0.0 && !is_zero {
+            formatted.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if options.use_grouping && i > 0 && (integer.len() - i) % 3 == 0 {
+                formatted.push_str(self.group_separator);
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push_str(self.decimal_separator);
+            formatted.push_str(&fraction);
+        }
+
+        match &options.style {
+            FluentNumberStyle::Percent => formatted.push_str(self.percent_sign),
+            FluentNumberStyle::Currency => {
+                if let Some(currency) = &options.currency {
+                    if self.identifier.language.as_str() == "en" {
+                        formatted = format!("{}\u{a0}{}", currency, formatted);
+                    } else {
+                        formatted = format!("{}\u{a0}{}", formatted, currency);
+                    }
+                }
+            }
+            FluentNumberStyle::Decimal => (),
+        }
+        formatted
+    }
+
+    /// Formats a date and time according to the locale's conventions.
+    ///
+    /// If neither a date nor a time style is given, the date is formatted in the medium style.
+    pub fn format_datetime(
+        &self,
+        datetime: &DateTime<FixedOffset>,
+        options: &DateTimeOptions,
+    ) -> String {
+        let date_style = match options {
+            DateTimeOptions {
+                date_style: None,
+                time_style: None,
+            } => Some(DateTimeStyle::Medium),
+            options => options.date_style,
+        };
+        let date = date_style.map(|style| self.format_date(datetime, style));
+        let time = options
+            .time_style
+            .map(|style| self.format_time(datetime, style));
+        match (date, time) {
+            (Some(date), Some(time)) => format!("{}{}{}", date, self.date_time_separator, time),
+            (Some(date), None) => date,
+            (None, Some(time)) => time,
+            (None, None) => unreachable!("date style is always set when time style is absent"),
+        }
+    }
+
+    fn format_date(&self, datetime: &DateTime<FixedOffset>, style: DateTimeStyle) -> String {
+        let (year, month, day) = (datetime.year(), datetime.month(), datetime.day());
+        if let (Some(months), DateTimeStyle::Long | DateTimeStyle::Full) = (self.month_names, style)
+        {
+            let month = months[month as usize - 1];
+            let date = match self.long_date {
+                LongDate::MonthFirst => format!("{} {}, {}", month, day, year),
+                LongDate::DayFirst => format!("{} {} {}", day, month, year),
+                LongDate::DayWithDot => format!("{}. {} {}", day, month, year),
+                LongDate::DayWithDe => format!("{} de {} de {}", day, month, year),
+            };
+            return match (self.weekday_names, style) {
+                (Some(weekdays), DateTimeStyle::Full) => {
+                    let weekday = weekdays[datetime.weekday().num_days_from_monday() as usize];
+                    format!("{}{}{}", weekday, self.weekday_separator, date)
+                }
+                _ => date,
+            };
+        }
+
+        let separator = self.date_separator;
+        let year = if self.short_year && style == DateTimeStyle::Short {
+            format!("{:02}", year.rem_euclid(100))
+        } else {
+            year.to_string()
+        };
+        match self.date_order {
+            DateOrder::DayMonthYear => {
+                format!("{:02}{sep}{:02}{sep}{}", day, month, year, sep = separator)
+            }
+            DateOrder::MonthDayYear => {
+                format!("{}{sep}{}{sep}{}", month, day, year, sep = separator)
+            }
+            DateOrder::YearMonthDay => {
+                format!("{}{sep}{:02}{sep}{:02}", year, month, day, sep = separator)
+            }
+        }
+    }
+
+    fn format_time(&self, datetime: &DateTime<FixedOffset>, style: DateTimeStyle) -> String {
+        let (minute, second) = (datetime.minute(), datetime.second());
+        let seconds = match style {
+            DateTimeStyle::Short => String::new(),
+            _ => format!(":{:02}", second),
+        };
+        match self.day_periods {
+            Some((am, pm)) => {
+                let (is_pm, hour) = datetime.hour12();
+                let period = if is_pm { pm } else { am };
+                format!("{}:{:02}{}\u{202f}{}", hour, minute, seconds, period)
+            }
+            None => format!("{:02}:{:02}{}", datetime.hour(), minute, seconds),
+        }
+    }
+}
+
+/// Locales are memoized per bundle, so that the table doesn't need to be consulted each time a
+/// value is formatted.
+impl Memoizable for Locale {
+    type Args = ();
+    type Error = Infallible;
+
+    fn construct(identifier: LanguageIdentifier, _args: ()) -> Result<Self, Infallible> {
+        Ok(Self::new(identifier))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use fluent::types::FluentNumberOptions;
+
+    use super::*;
+
+    fn locale(identifier: &str) -> Locale {
+        Locale::new(identifier.parse().unwrap())
+    }
+
+    fn number(locale: &Locale, value: f64) -> String {
+        locale.format_number(&FluentNumber::new(value, FluentNumberOptions::default()))
+    }
+
+    fn styled(
+        locale: &Locale,
+        value: f64,
+        style: FluentNumberStyle,
+        currency: Option<&str>,
+    ) -> String {
+        let options = FluentNumberOptions {
+            style,
+            currency: currency.map(|currency| currency.to_owned()),
+            ..Default::default()
+        };
+        locale.format_number(&FluentNumber::new(value, options))
+    }
+
+    /// Friday, 5 January 2024, 14:30:45.
+    fn datetime() -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2024, 1, 5, 14, 30, 45)
+            .unwrap()
+    }
+
+    fn date(locale: &Locale, style: DateTimeStyle) -> String {
+        let options = DateTimeOptions {
+            date_style: Some(style),
+            time_style: None,
+        };
+        locale.format_datetime(&datetime(), &options)
+    }
+
+    #[test]
+    fn numbers() {
+        let en = locale("en-US");
+        assert_eq!(number(&en, 1234567.891), "1,234,567.891");
+        assert_eq!(number(&en, 1234.56789), "1,234.568");
+        assert_eq!(number(&en, 1.0 / 3.0), "0.333");
+        assert_eq!(number(&en, 42.0), "42");
+        assert_eq!(number(&en, -1234.5), "-1,234.5");
+        // Values that round to zero don't keep their sign.
+        assert_eq!(number(&en, -0.0001), "0");
+
+        let de = locale("de");
+        assert_eq!(number(&de, 1234567.891), "1.234.567,891");
+        assert_eq!(number(&de, 1234.56789), "1.234,568");
+        assert_eq!(number(&de, -1234.5), "-1.234,5");
+
+        let fr = locale("fr");
+        assert_eq!(number(&fr, 1234567.891), "1\u{202f}234\u{202f}567,891");
+        assert_eq!(number(&fr, 1234.56789), "1\u{202f}234,568");
+        assert_eq!(number(&fr, -1234.5), "-1\u{202f}234,5");
+    }
+
+    #[test]
+    fn rounding_is_half_away_from_zero() {
+        let en = locale("en-US");
+        let options = FluentNumberOptions {
+            maximum_fraction_digits: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(
+            en.format_number(&FluentNumber::new(0.125, options.clone())),
+            "0.13"
+        );
+        assert_eq!(
+            en.format_number(&FluentNumber::new(-0.125, options)),
+            "-0.13"
+        );
+    }
+
+    #[test]
+    fn percent() {
+        let percent = FluentNumberStyle::Percent;
+        assert_eq!(styled(&locale("en-US"), 0.256, percent, None), "26%");
+        assert_eq!(styled(&locale("en-US"), -0.5, percent, None), "-50%");
+        assert_eq!(styled(&locale("de"), 0.256, percent, None), "26\u{a0}%");
+        assert_eq!(
+            styled(&locale("fr"), 12.5, percent, None),
+            "1\u{202f}250\u{a0}%"
+        );
+    }
+
+    #[test]
+    fn currency() {
+        let currency = FluentNumberStyle::Currency;
+        assert_eq!(
+            styled(&locale("en-US"), 1234.5, currency, Some("USD")),
+            "USD\u{a0}1,234.50"
+        );
+        assert_eq!(
+            styled(&locale("de"), 1234.5, currency, Some("EUR")),
+            "1.234,50\u{a0}EUR"
+        );
+        assert_eq!(
+            styled(&locale("fr"), -3.0, currency, Some("EUR")),
+            "-3,00\u{a0}EUR"
+        );
+    }
+
+    #[test]
+    fn date_styles() {
+        let en = locale("en-US");
+        assert_eq!(date(&en, DateTimeStyle::Short), "1/5/24");
+        assert_eq!(date(&en, DateTimeStyle::Medium), "1/5/2024");
+        assert_eq!(date(&en, DateTimeStyle::Long), "January 5, 2024");
+        assert_eq!(date(&en, DateTimeStyle::Full), "Friday, January 5, 2024");
+
+        let de = locale("de");
+        assert_eq!(date(&de, DateTimeStyle::Short), "05.01.24");
+        assert_eq!(date(&de, DateTimeStyle::Medium), "05.01.2024");
+        assert_eq!(date(&de, DateTimeStyle::Long), "5. Januar 2024");
+        assert_eq!(date(&de, DateTimeStyle::Full), "Freitag, 5. Januar 2024");
+
+        let fr = locale("fr");
+        assert_eq!(date(&fr, DateTimeStyle::Short), "05/01/2024");
+        assert_eq!(date(&fr, DateTimeStyle::Medium), "05/01/2024");
+        assert_eq!(date(&fr, DateTimeStyle::Long), "5 janvier 2024");
+        assert_eq!(date(&fr, DateTimeStyle::Full), "vendredi 5 janvier 2024");
+    }
+
+    #[test]
+    fn times() {
+        let short = DateTimeOptions {
+            date_style: None,
+            time_style: Some(DateTimeStyle::Short),
+        };
+        let both = DateTimeOptions {
+            date_style: Some(DateTimeStyle::Medium),
+            time_style: Some(DateTimeStyle::Medium),
+        };
+        let en = locale("en-US");
+        assert_eq!(en.format_datetime(&datetime(), &short), "2:30\u{202f}PM");
+        assert_eq!(
+            en.format_datetime(&datetime(), &both),
+            "1/5/2024, 2:30:45\u{202f}PM"
+        );
+        // Without any styles, only the date is formatted.
+        assert_eq!(
+            en.format_datetime(&datetime(), &DateTimeOptions::default()),
+            "1/5/2024"
+        );
+
+        let de = locale("de");
+        assert_eq!(de.format_datetime(&datetime(), &short), "14:30");
+        assert_eq!(
+            de.format_datetime(&datetime(), &both),
+            "05.01.2024 14:30:45"
+        );
+    }
+}
@@ -0,0 +1,9 @@
+## Render backend errors
+
+error-backend-cannot-initialize-backend =
+    Failed to create OpenGL context.
+    Tried the following configurations, none of which seem to be supported:
+    { $tried }
+    Try updating your graphics drivers. If that doesn't help, the app is too new to run on your hardware!
+error-backend-cannot-initialize-freetype = FreeType error: { $error }
+error-backend-context = OpenGL context error: { $error }
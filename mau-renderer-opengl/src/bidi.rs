@@ -0,0 +1,80 @@
+//! Bidirectional text support.
+
+use std::borrow::Cow;
+
+use mau_renderer::TextDirection;
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level};
+
+/// Reorders text from logical to visual order according to the Unicode bidirectional algorithm,
+/// so that it can be typeset from left to right.
+///
+/// Every paragraph uses the given base direction, rather than one detected from its first strong
+/// character, so that eg. a label starting with an English name is laid out right-to-left in an
+/// Arabic UI. Characters in right-to-left runs have their mirrored counterparts substituted, so
+/// that eg. parentheses face the right way.
+pub(crate) fn reorder(text: &str, base: TextDirection) -> Cow<'_, str> {
+    // Pure ASCII text cannot contain any right-to-left characters, but in a right-to-left
+    // paragraph its neutral characters may still move.
+    if base == TextDirection::LeftToRight && text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+
+    let level = match base {
+        TextDirection::LeftToRight => Level::ltr(),
+        TextDirection::RightToLeft => Level::rtl(),
+    };
+    let bidi = BidiInfo::new(text, Some(level));
+    if !bidi.has_rtl() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut visual = String::with_capacity(text.len());
+    for paragraph in &bidi.paragraphs {
+        let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let run_text = &text[run.clone()];
+            if levels[run.start].is_rtl() {
+                visual.extend(run_text.chars().rev().map(mirror));
+            } else {
+                visual.push_str(run_text);
+            }
+        }
+    }
+    Cow::Owned(visual)
+}
+
+/// Returns whether the character is an invisible bidi formatting character, which should not be
+/// rendered.
+pub(crate) fn is_control(c: char) -> bool {
+    matches!(
+        bidi_class(c),
+        BidiClass::LRE
+            | BidiClass::RLE
+            | BidiClass::PDF
+            | BidiClass::LRO
+            | BidiClass::RLO
+            | BidiClass::LRI
+            | BidiClass::RLI
+            | BidiClass::FSI
+            | BidiClass::PDI
+    ) || matches!(c, '\u{200E}' | '\u{200F}' | '\u{061C}')
+}
+
+/// Returns the mirrored counterpart of a character displayed in a right-to-left run.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        c => c,
+    }
+}
@@ -1,12 +1,13 @@
+use mau_i18n::{FormatArg, TranslateEnum};
 use thiserror::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ContextConfiguration {
     pub msaa: u16,
     pub error: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TriedConfigurations(pub Vec<ContextConfiguration>);
 
 impl std::fmt::Display for TriedConfigurations {
@@ -18,12 +19,18 @@ impl std::fmt::Display for TriedConfigurations {
     }
 }
 
-#[derive(Debug, Error)]
+impl From<TriedConfigurations> for FormatArg<'_> {
+    fn from(tried: TriedConfigurations) -> Self {
+        tried.to_string().into()
+    }
+}
+
+#[derive(Debug, Error, TranslateEnum)]
+#[prefix = "error-backend"]
+#[ftl("i18n/en-US.ftl")]
+#[ftl_display]
 pub enum Error {
-    #[error("Failed to create OpenGL context. \nTried the following configurations, none of which seem to be supported:\n{0}\nTry updating your graphics drivers. If that doesn't help, the app is too new to run on your hardware!")]
-    CannotInitializeBackend(TriedConfigurations),
-    #[error("FreeType error: {0}")]
+    CannotInitializeBackend(#[arg = "tried"] TriedConfigurations),
     CannotInitializeFreetype(#[from] freetype::Error),
-    #[error("OpenGL context error: {0}")]
     Context(#[from] glutin::ContextError),
 }
@@ -16,7 +16,7 @@ use mau_renderer::paws::{vector, Rect, Vector};
 
 use crate::common::{GlUtilities, RectMath};
 use crate::rect_packer::RectPacker;
-use crate::Error;
+use crate::{bidi, Error};
 
 const TEXTURE_ATLAS_SIZE: u32 = 1024;
 
@@ -274,7 +274,7 @@ impl<'font, 'text> Typeset<'font, 'text> {
     /// position is calculated, without any of the intermediate glyph positions.
     pub fn fast_forward(mut self) -> f32 {
         let mut renderer = self.store.glyph_renderer(self.font.size);
-        for c in self.text.by_ref() {
+        for c in self.text.by_ref().filter(|&c| !bidi::is_control(c)) {
             if let Ok(glyph) = renderer.get_or_render_glyph(c) {
                 self.pen_x += glyph.advance_x;
             }
@@ -287,7 +287,7 @@ impl<'font, 'text> Iterator for Typeset<'font, 'text> {
     type Item = (Rect, Rect);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(c) = self.text.next() {
+        if let Some(c) = self.text.find(|&c| !bidi::is_control(c)) {
             //    Hopefully this gets hoisted out of the loop, albeit it's not that expensive in the
             // ↓ first place.
             let mut renderer = self.store.glyph_renderer(self.font.size);
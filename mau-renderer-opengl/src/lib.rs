@@ -1,3 +1,4 @@
+mod bidi;
 mod common;
 mod error;
 mod font;
@@ -15,6 +16,7 @@ use glutin::{
     WindowedContext,
 };
 use mau_renderer::paws::Ui;
+use mau_renderer::TextDirection;
 use rendering::RenderState;
 pub use winit;
 use winit::event_loop::EventLoop;
@@ -25,12 +27,16 @@ pub use crate::framebuffer::Framebuffer;
 pub use crate::image::Image;
 pub use error::*;
 
+/// The backend's built-in translations, which apps have to load alongside their own.
+pub const FTL: &str = include_str!("../i18n/en-US.ftl");
+
 pub struct OpenGlBackend {
     context: WindowedContext<PossiblyCurrent>,
     context_size: PhysicalSize<u32>,
     pub(crate) gl: Rc<glow::Context>,
     pub(crate) freetype: Rc<freetype::Library>,
     state: RenderState,
+    text_direction: TextDirection,
 }
 
 impl OpenGlBackend {
@@ -92,6 +98,7 @@ impl OpenGlBackend {
             state: RenderState::new(Rc::clone(&gl)),
             freetype: Rc::new(freetype::Library::init()?),
             gl,
+            text_direction: TextDirection::default(),
         })
     }
 
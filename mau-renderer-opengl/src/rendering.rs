@@ -12,9 +12,12 @@ use glow::HasContext;
 use mau_renderer::paws::{
     point, vector, AlignH, AlignV, Alignment, Color, LineCap, Point, Rect, Renderer, Vector,
 };
-use mau_renderer::{BlendMode, Font as FontTrait, Framebuffer as FramebufferTrait, RenderBackend};
+use mau_renderer::{
+    BlendMode, Font as FontTrait, Framebuffer as FramebufferTrait, RenderBackend, TextDirection,
+};
 use memoffset::offset_of;
 
+use crate::bidi;
 use crate::common::{normalized_color, to_vec2, GlUtilities, VectorMath};
 use crate::font::Font;
 use crate::framebuffer::Framebuffer;
@@ -433,8 +436,23 @@ impl RenderState {
     }
 }
 
-fn text_origin(rect: &Rect, font: &Font, text: &str, alignment: Alignment) -> Point {
-    let x = match alignment.0 {
+/// Computes the pen origin of a line of text.
+///
+/// In a right-to-left UI, text is aligned to the opposite side of the rectangle, so that it starts
+/// where the reader expects it to.
+fn text_origin(
+    rect: &Rect,
+    font: &Font,
+    text: &str,
+    alignment: Alignment,
+    direction: TextDirection,
+) -> Point {
+    let align_h = match (alignment.0, direction) {
+        (AlignH::Left, TextDirection::RightToLeft) => AlignH::Right,
+        (AlignH::Right, TextDirection::RightToLeft) => AlignH::Left,
+        (align_h, _) => align_h,
+    };
+    let x = match align_h {
         AlignH::Left => rect.left(),
         AlignH::Center => rect.center_x() - font.text_width(text) / 2.0,
         AlignH::Right => rect.right() - font.text_width(text),
@@ -794,9 +812,10 @@ impl Renderer for OpenGlBackend {
         }
 
         // Buffer up the glyphs.
-        let origin = text_origin(&rect, font, text, alignment);
+        let visual = bidi::reorder(text, self.text_direction);
+        let origin = text_origin(&rect, font, &visual, alignment, self.text_direction);
         self.start();
-        for (mut position, uv) in font.typeset(text) {
+        for (mut position, uv) in font.typeset(&visual) {
             position.position += origin;
             self.shape().rect(
                 Vertex::textured_colored(position.top_left(), uv.top_left(), color),
@@ -919,4 +938,8 @@ impl RenderBackend for OpenGlBackend {
         self.state.transform_mut().blend_mode = new_blend_mode;
         self.state.apply_transform();
     }
+
+    fn set_text_direction(&mut self, direction: TextDirection) {
+        self.text_direction = direction;
+    }
 }
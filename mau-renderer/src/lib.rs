@@ -35,6 +35,16 @@ impl Default for ScalingFilter {
     }
 }
 
+/// The base direction of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    /// Left-to-right. The default direction.
+    #[default]
+    LeftToRight,
+    /// Right-to-left.
+    RightToLeft,
+}
+
 /// An image.
 pub trait Image {
     /// _Colorizes_ an image by replacing all of its color with a single, solid color.
@@ -151,6 +161,14 @@ pub trait RenderBackend: Renderer {
     /// the change is completely transparent to outside code.
     fn set_blend_mode(&mut self, new_blend_mode: BlendMode);
 
+    /// Sets the base direction of text, which should be the direction of the UI's language.
+    ///
+    /// The base direction determines how mixed-direction text is ordered, and mirrors horizontal
+    /// text alignment: in right-to-left UIs, [`AlignH::Left`][paws::AlignH::Left] aligns text to the right edge.
+    ///
+    /// The default implementation does nothing, which leaves text laid out left-to-right.
+    fn set_text_direction(&mut self, _direction: TextDirection) {}
+
     /// Draws a filled circle, with the given center point, radius, and color.
     fn fill_circle(&mut self, center: Point, radius: f32, color: Color) {
         self.fill(
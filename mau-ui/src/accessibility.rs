@@ -0,0 +1,371 @@
+//! Accessibility tree, for exposing the UI to screen readers.
+//!
+//! paws layouts don't carry any semantic information, so widgets and app states describe what
+//! they draw by adding [`Node`]s to an [`AccessibilityTree`] while they're being processed. At the
+//! end of each frame the tree is handed over to an [`AccessibilityAdapter`], which translates it
+//! into whatever the platform's accessibility API expects.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::paws::{point, vector, Rect};
+
+/// The role of a node, ie. what kind of widget it represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// The root of the tree.
+    Window,
+    /// A group of nodes with no other meaning.
+    Group,
+    /// Non-interactive text.
+    Label,
+    Button,
+    CheckBox,
+    TextInput,
+    Slider,
+    List,
+    ListItem,
+    Menu,
+    MenuItem,
+    Image,
+    Dialog,
+    /// A freeform drawing area.
+    Canvas,
+}
+
+/// An action that can be performed on a node by assistive technology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeAction {
+    Click,
+    Focus,
+    Increment,
+    Decrement,
+    ScrollIntoView,
+}
+
+/// A unique identifier of a node.
+///
+/// IDs are derived from the node's position in the tree (or its key, if it has one), so they stay
+/// the same across frames as long as the UI's structure doesn't change. If two siblings share the
+/// same key, the later one's ID is derived from its position instead, so IDs are always unique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub u64);
+
+/// A single node in the accessibility tree.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// What kind of widget the node represents.
+    pub role: Role,
+    /// The human-readable label, usually taken from the app's translated strings.
+    pub label: String,
+    /// The current value of the node, eg. the text inside of a text input.
+    pub value: Option<String>,
+    /// The node's bounding rectangle, in window coordinates.
+    pub bounds: Rect,
+    /// Actions that the node supports.
+    pub actions: Vec<NodeAction>,
+    /// Whether the node has keyboard focus.
+    pub focused: bool,
+    key: Option<u64>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl Node {
+    /// Creates a new node with the given role and label.
+    pub fn new(role: Role, label: impl Into<String>) -> Self {
+        Self {
+            role,
+            label: label.into(),
+            value: None,
+            bounds: Rect::new(point(0.0, 0.0), vector(0.0, 0.0)),
+            actions: Vec::new(),
+            focused: false,
+            key: None,
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Sets the node's bounding rectangle.
+    pub fn with_bounds(mut self, bounds: Rect) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Sets the node's value.
+    pub fn with_value(mut self, value: impl Into<String>) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Adds a supported action to the node.
+    pub fn with_action(mut self, action: NodeAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Marks the node as focused.
+    pub fn with_focus(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// Sets a key used for deriving the node's ID, in place of its index among its siblings.
+    ///
+    /// This should be used for nodes in dynamic lists, so that their IDs don't change when
+    /// elements before them are inserted or removed.
+    pub fn with_key(mut self, key: impl Hash) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.key = Some(hasher.finish());
+        self
+    }
+
+    /// Returns the ID of the node's parent, or `None` if this is the root node.
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Returns the IDs of the node's children.
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// An action request sent by assistive technology.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionRequest {
+    pub target: NodeId,
+    pub action: NodeAction,
+}
+
+/// The accessibility tree, rebuilt every frame.
+pub struct AccessibilityTree {
+    nodes: Vec<Node>,
+    ids: Vec<NodeId>,
+    stack: Vec<usize>,
+    requests: Vec<ActionRequest>,
+}
+
+impl AccessibilityTree {
+    /// Creates a new, empty tree.
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            ids: Vec::new(),
+            stack: Vec::new(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Begins a new frame. This clears the tree and adds the root window node, labelled with
+    /// `window_label`.
+    ///
+    /// `requests` are the action requests received from the adapter since the last frame; they
+    /// can be queried using [`action_requested`][Self::action_requested].
+    pub fn begin_frame(
+        &mut self,
+        window_label: impl Into<String>,
+        window_bounds: Rect,
+        requests: Vec<ActionRequest>,
+    ) {
+        self.nodes.clear();
+        self.ids.clear();
+        self.stack.clear();
+        self.requests = requests;
+        self.push(Node::new(Role::Window, window_label).with_bounds(window_bounds));
+    }
+
+    /// Finishes the frame. Any nodes that weren't popped are popped automatically.
+    pub fn finish_frame(&mut self) {
+        self.stack.truncate(1);
+    }
+
+    fn add(&mut self, mut node: Node) -> usize {
+        let index = self.nodes.len();
+        let parent = self.stack.last().copied();
+        let siblings: &[NodeId] = match parent {
+            Some(parent) => &self.nodes[parent].children,
+            None => &[],
+        };
+        let hash_id = |key: Option<u64>| {
+            let mut hasher = DefaultHasher::new();
+            parent.map(|parent| self.ids[parent]).hash(&mut hasher);
+            match key {
+                Some(key) => key.hash(&mut hasher),
+                None => (node.role, siblings.len()).hash(&mut hasher),
+            }
+            NodeId(hasher.finish())
+        };
+        let id = match hash_id(node.key) {
+            id if siblings.contains(&id) => hash_id(None),
+            id => id,
+        };
+        node.parent = parent.map(|parent| self.ids[parent]);
+        node.children.clear();
+        if let Some(parent) = parent {
+            self.nodes[parent].children.push(id);
+        }
+        self.nodes.push(node);
+        self.ids.push(id);
+        index
+    }
+
+    /// Adds a node to the tree and makes it the parent of all subsequently added nodes, until
+    /// [`pop`][Self::pop] is called.
+    pub fn push(&mut self, node: Node) -> NodeId {
+        let index = self.add(node);
+        self.stack.push(index);
+        self.ids[index]
+    }
+
+    /// Pops the current parent node off the stack.
+    ///
+    /// # Panics
+    ///
+    /// If there's no node left to pop other than the root.
+    pub fn pop(&mut self) {
+        assert!(self.stack.len() > 1, "cannot pop the root node");
+        self.stack.pop();
+    }
+
+    /// Adds a node without any children to the tree.
+    pub fn leaf(&mut self, node: Node) -> NodeId {
+        let index = self.add(node);
+        self.ids[index]
+    }
+
+    /// Returns whether assistive technology requested the given action to be performed on the
+    /// node with the given ID.
+    pub fn action_requested(&self, id: NodeId, action: NodeAction) -> bool {
+        self.requests
+            .iter()
+            .any(|request| request.target == id && request.action == action)
+    }
+
+    /// Returns the ID of the root node.
+    ///
+    /// # Panics
+    ///
+    /// If called before the first frame was begun.
+    pub fn root(&self) -> NodeId {
+        self.ids[0]
+    }
+
+    /// Returns the node with the given ID.
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.ids
+            .iter()
+            .position(|&other| other == id)
+            .map(|index| &self.nodes[index])
+    }
+
+    /// Returns an iterator over all nodes in the tree, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.ids.iter().copied().zip(self.nodes.iter())
+    }
+
+    /// Returns the first node with the given role and label.
+    pub fn find(&self, role: Role, label: &str) -> Option<(NodeId, &Node)> {
+        self.iter()
+            .find(|(_, node)| node.role == role && node.label == label)
+    }
+
+    /// Returns the node that currently has keyboard focus.
+    pub fn focus(&self) -> Option<(NodeId, &Node)> {
+        self.iter().find(|(_, node)| node.focused)
+    }
+}
+
+impl Default for AccessibilityTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An adapter between the accessibility tree and the platform's accessibility API.
+///
+/// mau does not ship any adapters by itself; an AccessKit-based adapter can be implemented by
+/// converting nodes to AccessKit nodes in [`update`][Self::update].
+pub trait AccessibilityAdapter {
+    /// Updates the platform's view of the UI after a frame is finished.
+    fn update(&mut self, tree: &AccessibilityTree);
+
+    /// Returns any action requests that were received since the last frame.
+    fn take_requests(&mut self) -> Vec<ActionRequest> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn begin(tree: &mut AccessibilityTree) {
+        let bounds = Rect::new(point(0.0, 0.0), vector(800.0, 600.0));
+        tree.begin_frame("Test", bounds, Vec::new());
+    }
+
+    fn build(tree: &mut AccessibilityTree, items: &[&str]) {
+        begin(tree);
+        tree.push(Node::new(Role::List, "Layers"));
+        for &item in items {
+            tree.leaf(Node::new(Role::ListItem, item).with_key(item));
+        }
+        tree.pop();
+        tree.leaf(Node::new(Role::Button, "Save").with_action(NodeAction::Click));
+        tree.finish_frame();
+    }
+
+    #[test]
+    fn tree_structure() {
+        let mut tree = AccessibilityTree::new();
+        build(&mut tree, &["Background", "Sketch"]);
+
+        let root = tree.node(tree.root()).unwrap();
+        assert_eq!(root.role, Role::Window);
+        assert_eq!(root.label, "Test");
+        assert_eq!(root.children().len(), 2);
+
+        let (list_id, list) = tree.find(Role::List, "Layers").unwrap();
+        assert_eq!(list.parent(), Some(tree.root()));
+        let labels: Vec<_> = list
+            .children()
+            .iter()
+            .map(|&id| tree.node(id).unwrap().label.as_str())
+            .collect();
+        assert_eq!(labels, ["Background", "Sketch"]);
+
+        let (_, save) = tree.find(Role::Button, "Save").unwrap();
+        assert_eq!(save.parent(), Some(tree.root()));
+        assert_eq!(save.actions, [NodeAction::Click]);
+        assert_ne!(root.children()[0], root.children()[1]);
+        assert_eq!(root.children()[0], list_id);
+    }
+
+    #[test]
+    fn ids_are_stable() {
+        let mut tree = AccessibilityTree::new();
+        build(&mut tree, &["Background", "Sketch"]);
+        let (sketch, _) = tree.find(Role::ListItem, "Sketch").unwrap();
+        let (save, _) = tree.find(Role::Button, "Save").unwrap();
+
+        // Keyed nodes keep their IDs when nodes before them are removed.
+        build(&mut tree, &["Sketch"]);
+        assert_eq!(tree.find(Role::ListItem, "Sketch").unwrap().0, sketch);
+        assert_eq!(tree.find(Role::Button, "Save").unwrap().0, save);
+    }
+
+    #[test]
+    fn duplicate_keys_get_unique_ids() {
+        let mut tree = AccessibilityTree::new();
+        build(&mut tree, &["Sketch", "Sketch", "Sketch"]);
+        let (_, list) = tree.find(Role::List, "Layers").unwrap();
+        let children = list.children();
+        assert_eq!(children.len(), 3);
+        assert_ne!(children[0], children[1]);
+        assert_ne!(children[1], children[2]);
+        assert_ne!(children[0], children[2]);
+    }
+}
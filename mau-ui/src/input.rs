@@ -2,11 +2,13 @@
 
 use instant::Instant;
 use std::ops::{BitAnd, BitOr};
+use std::time::Duration;
 
 use crate::backend::winit::dpi::PhysicalPosition;
 pub use crate::backend::winit::event::{ElementState, MouseButton, VirtualKeyCode};
 use crate::backend::winit::event::{KeyboardInput, WindowEvent};
 use crate::backend::winit::window::{CursorIcon, Window};
+use crate::keymap::{Binding, KeySequence, Keymap, MAX_SEQUENCE_LENGTH};
 use crate::paws::{point, vector, Point, Vector};
 use serde::de::Visitor;
 use serde::ser::SerializeSeq;
@@ -15,6 +17,9 @@ use serde::{Deserialize, Serialize};
 const MOUSE_BUTTON_COUNT: usize = 8;
 const KEY_CODE_COUNT: usize = 256;
 
+/// The maximum time between two consecutive bindings of a key sequence.
+const KEY_SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1500);
+
 /// Input state.
 pub struct Input {
     // mouse input
@@ -38,6 +43,12 @@ pub struct Input {
 
     key_just_typed: [bool; KEY_CODE_COUNT],
     key_is_down: [bool; KEY_CODE_COUNT],
+    recent_bindings: Vec<(Binding, Instant)>,
+    bindings_typed_this_frame: usize,
+
+    // scopes
+    active_scopes: Vec<Scope>,
+    frame_scopes: Vec<Scope>,
 
     // time
     time_origin: Instant,
@@ -65,6 +76,11 @@ impl Input {
             char_buffer: Vec::new(),
             key_just_typed: [false; KEY_CODE_COUNT],
             key_is_down: [false; KEY_CODE_COUNT],
+            recent_bindings: Vec::new(),
+            bindings_typed_this_frame: 0,
+
+            active_scopes: vec![Scope::Global],
+            frame_scopes: vec![Scope::Global],
 
             time_origin: Instant::now(),
         }
@@ -186,6 +202,65 @@ impl Input {
         }
     }
 
+    /// Returns whether the given key sequence was completed during this frame.
+    ///
+    /// Bindings in a sequence must be typed one after another, with no other keys typed in
+    /// between, and with no more than 1.5 seconds passing between each binding.
+    pub fn key_sequence_just_completed(&self, sequence: &KeySequence) -> bool {
+        self.bindings_typed_this_frame > 0
+            && self
+                .recent_bindings
+                .iter()
+                .map(|(binding, _)| binding)
+                .rev()
+                .take(sequence.bindings().len())
+                .eq(sequence.bindings().iter().rev())
+    }
+
+    /// Returns whether the action was triggered in the given keymap during this frame.
+    ///
+    /// See [`Keymap::triggered`].
+    pub fn triggered<A>(&self, keymap: &Keymap<A>, action: &A) -> bool
+    where
+        A: PartialEq,
+    {
+        keymap.triggered(self, action)
+    }
+
+    /// Marks the scope as active.
+    ///
+    /// Like mouse areas, scopes take effect on the next frame, so that the order in which things
+    /// are processed during a frame doesn't matter. Scopes must be entered on every frame during
+    /// which they should stay active, eg. a text field enters [`Scope::TextInput`] on every frame
+    /// while it's focused. [`Scope::Global`] is always active.
+    pub fn enter_scope(&mut self, scope: Scope) {
+        if let Err(index) = self.frame_scopes.binary_search(&scope) {
+            self.frame_scopes.insert(index, scope);
+        }
+    }
+
+    /// Returns the currently active scopes, ordered from lowest to highest priority.
+    pub fn active_scopes(&self) -> &[Scope] {
+        &self.active_scopes
+    }
+
+    /// Returns whether the scope is currently active.
+    pub fn scope_is_active(&self, scope: Scope) -> bool {
+        self.active_scopes.binary_search(&scope).is_ok()
+    }
+
+    /// Returns whether the key can be received in the given scope, ie. whether the scope is
+    /// active and no active scope with higher priority consumes the key.
+    pub fn key_available(&self, scope: Scope, key: VirtualKeyCode) -> bool {
+        let modifier = Modifier::from_input(self);
+        self.scope_is_active(scope)
+            && self
+                .active_scopes
+                .iter()
+                .filter(|&&other| other > scope)
+                .all(|other| !other.consumes(modifier, key))
+    }
+
     /// Returns whether the Ctrl key is being held down.
     pub fn ctrl_is_down(&self) -> bool {
         self.key_is_down(VirtualKeyCode::LControl) || self.key_is_down(VirtualKeyCode::RControl)
@@ -196,6 +271,16 @@ impl Input {
         self.key_is_down(VirtualKeyCode::LShift) || self.key_is_down(VirtualKeyCode::RShift)
     }
 
+    /// Returns whether the Alt key is being held down.
+    pub fn alt_is_down(&self) -> bool {
+        self.key_is_down(VirtualKeyCode::LAlt) || self.key_is_down(VirtualKeyCode::RAlt)
+    }
+
+    /// Returns whether the logo key (Windows, Command, Super) is being held down.
+    pub fn logo_is_down(&self) -> bool {
+        self.key_is_down(VirtualKeyCode::LWin) || self.key_is_down(VirtualKeyCode::RWin)
+    }
+
     /// Returns the time elapsed since this `Input` was created, in seconds.
     pub fn time_in_seconds(&self) -> f32 {
         let now = self.time_origin.elapsed();
@@ -273,6 +358,10 @@ impl Input {
             *state = false;
         }
         self.char_buffer.clear();
+        self.bindings_typed_this_frame = 0;
+        std::mem::swap(&mut self.active_scopes, &mut self.frame_scopes);
+        self.frame_scopes.clear();
+        self.frame_scopes.push(Scope::Global);
     }
 
     /// Returns the numeric index of the mouse given button, or `None` if the mouse button is not
@@ -319,10 +408,48 @@ impl Input {
         }
     }
 
+    /// Returns whether the key is a modifier key.
+    fn is_modifier_key(key: VirtualKeyCode) -> bool {
+        matches!(
+            key,
+            VirtualKeyCode::LControl
+                | VirtualKeyCode::RControl
+                | VirtualKeyCode::LShift
+                | VirtualKeyCode::RShift
+                | VirtualKeyCode::LAlt
+                | VirtualKeyCode::RAlt
+                | VirtualKeyCode::LWin
+                | VirtualKeyCode::RWin
+        )
+    }
+
+    /// Appends a typed binding to the list of recently typed bindings, forgetting bindings that
+    /// can no longer be part of a key sequence.
+    fn record_binding(&mut self, binding: Binding) {
+        let now = Instant::now();
+        let timed_out = self
+            .recent_bindings
+            .last()
+            .map(|&(_, time)| now.duration_since(time) > KEY_SEQUENCE_TIMEOUT)
+            .unwrap_or(false);
+        if timed_out {
+            self.recent_bindings.clear();
+        }
+        if self.recent_bindings.len() >= MAX_SEQUENCE_LENGTH {
+            self.recent_bindings.remove(0);
+        }
+        self.recent_bindings.push((binding, now));
+        self.bindings_typed_this_frame += 1;
+    }
+
     /// Processes a keyboard input event.
     fn process_keyboard_input(&mut self, key: VirtualKeyCode, state: ElementState) {
         if let Some(i) = Self::key_index(key) {
             if state == ElementState::Pressed {
+                // Key repeat shouldn't count as typing the binding multiple times.
+                if !self.key_is_down[i] && !Self::is_modifier_key(key) {
+                    self.record_binding(Binding::new(Modifier::from_input(self), key));
+                }
                 self.key_just_typed[i] = true;
                 self.key_is_down[i] = true;
             }
@@ -344,6 +471,91 @@ impl Default for Input {
 // Actions
 //
 
+/// A scope in which input actions are checked.
+///
+/// Active scopes form a stack ordered by priority, from [`Global`][Self::Global] at the bottom to
+/// [`TextInput`][Self::TextInput] at the top. Scopes higher up the stack can consume keys, so that
+/// they're not received by actions in the scopes below, eg. a focused text field consumes letter
+/// keys so that typing doesn't trigger canvas tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Scope {
+    /// Always active, and doesn't consume any keys.
+    Global,
+    /// A drawing area, eg. the main canvas of a paint app. Doesn't consume any keys.
+    Canvas,
+    /// A modal dialog. Consumes all keys.
+    Modal,
+    /// A focused text field. Consumes all keys used for typing and editing text, unless Ctrl is
+    /// held down.
+    TextInput,
+}
+
+impl Scope {
+    /// Returns whether the scope prevents the key from being received in lower scopes while it's
+    /// active.
+    fn consumes(self, modifier: Modifier, key: VirtualKeyCode) -> bool {
+        match self {
+            Self::Global | Self::Canvas => false,
+            Self::Modal => true,
+            Self::TextInput => !modifier.ctrl() && Self::is_text_key(key),
+        }
+    }
+
+    /// Returns whether the key is used for typing or editing text.
+    fn is_text_key(key: VirtualKeyCode) -> bool {
+        use VirtualKeyCode::*;
+        // Key1..=Key0 and A..=Z are contiguous in the enum.
+        (Key1..=Z).contains(&key)
+            || matches!(
+                key,
+                Space
+                    | Back
+                    | Delete
+                    | Return
+                    | NumpadEnter
+                    | Left
+                    | Right
+                    | Up
+                    | Down
+                    | Home
+                    | End
+                    | Numpad0
+                    | Numpad1
+                    | Numpad2
+                    | Numpad3
+                    | Numpad4
+                    | Numpad5
+                    | Numpad6
+                    | Numpad7
+                    | Numpad8
+                    | Numpad9
+                    | NumpadAdd
+                    | NumpadSubtract
+                    | NumpadMultiply
+                    | NumpadDivide
+                    | NumpadDecimal
+                    | NumpadComma
+                    | NumpadEquals
+                    | Apostrophe
+                    | Asterisk
+                    | At
+                    | Backslash
+                    | Colon
+                    | Comma
+                    | Equals
+                    | Grave
+                    | LBracket
+                    | Minus
+                    | Period
+                    | Plus
+                    | RBracket
+                    | Semicolon
+                    | Slash
+                    | Underline
+            )
+    }
+}
+
 /// A basic input action. This includes key presses, mouse clicks, etc., without modifier keys.
 pub trait BasicAction {
     /// The result of the action. Usually a `bool`, but some actions, eg. mouse scrolling, can
@@ -352,6 +564,30 @@ pub trait BasicAction {
 
     /// Checks whether the action is now being performed.
     fn check(&self, input: &Input) -> Self::Result;
+
+    /// Checks whether the action is now being performed in the given scope.
+    ///
+    /// The default implementation ignores the scope. [`check`][Self::check] is the same as
+    /// checking in [`Scope::Global`].
+    fn check_in(&self, input: &Input, scope: Scope) -> Self::Result {
+        let _ = scope;
+        self.check(input)
+    }
+}
+
+/// A basic action checked in the given scope, rather than the global scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scoped<A>(pub Scope, pub A);
+
+impl<A> BasicAction for Scoped<A>
+where
+    A: BasicAction,
+{
+    type Result = A::Result;
+
+    fn check(&self, input: &Input) -> Self::Result {
+        self.1.check_in(input, self.0)
+    }
 }
 
 /// The state of a mouse button.
@@ -387,7 +623,11 @@ impl BasicAction for VirtualKeyCode {
     type Result = bool;
 
     fn check(&self, input: &Input) -> Self::Result {
-        input.key_just_typed(*self)
+        self.check_in(input, Scope::Global)
+    }
+
+    fn check_in(&self, input: &Input, scope: Scope) -> Self::Result {
+        input.key_just_typed(*self) && input.key_available(scope, *self)
     }
 }
 
@@ -421,6 +661,16 @@ where
             r
         })
     }
+
+    /// Checks all basic actions in the given scope, and returns their results in an array.
+    fn check_in(&self, input: &Input, scope: Scope) -> Self::Result {
+        let mut i = 0;
+        [0; N].map(|_| {
+            let r = self[i].check_in(input, scope);
+            i += 1;
+            r
+        })
+    }
 }
 
 /// A full input action. This includes all basic actions, and actions with modifier keys.
@@ -445,7 +695,7 @@ impl Input {
 }
 
 /// A set of modifier keys.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct Modifier(u8);
 
@@ -457,9 +707,23 @@ impl Modifier {
     pub const SHIFT: Self = Self(0b1);
     /// The Ctrl key.
     pub const CTRL: Self = Self(0b10);
-
-    const SHIFT_STR: &'static str = "Shift";
-    const CTRL_STR: &'static str = "Ctrl";
+    /// The Alt key.
+    pub const ALT: Self = Self(0b100);
+    /// The logo key, ie. Windows, Command, or Super.
+    pub const LOGO: Self = Self(0b1000);
+
+    pub(crate) const SHIFT_STR: &'static str = "Shift";
+    pub(crate) const CTRL_STR: &'static str = "Ctrl";
+    pub(crate) const ALT_STR: &'static str = "Alt";
+    pub(crate) const LOGO_STR: &'static str = "Logo";
+
+    /// All modifiers along with their names, in the order they're written in.
+    pub(crate) const NAMED: [(Self, &'static str); 4] = [
+        (Self::CTRL, Self::CTRL_STR),
+        (Self::ALT, Self::ALT_STR),
+        (Self::SHIFT, Self::SHIFT_STR),
+        (Self::LOGO, Self::LOGO_STR),
+    ];
 
     /// Creates modifiers from the given input.
     pub fn from_input(input: &Input) -> Self {
@@ -470,9 +734,28 @@ impl Modifier {
         if input.ctrl_is_down() {
             mods = mods | Self::CTRL;
         }
+        if input.alt_is_down() {
+            mods = mods | Self::ALT;
+        }
+        if input.logo_is_down() {
+            mods = mods | Self::LOGO;
+        }
         mods
     }
 
+    /// Returns the modifier with the given name, eg. `Ctrl`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::NAMED
+            .iter()
+            .find(|(_, other)| *other == name)
+            .map(|&(modifier, _)| modifier)
+    }
+
+    /// Returns whether all modifiers in `other` are included in this set.
+    pub fn contains(&self, other: Self) -> bool {
+        (*self & other) == other
+    }
+
     /// Returns whether the shift key is included in this set.
     pub fn shift(&self) -> bool {
         (*self & Self::SHIFT) == Self::SHIFT
@@ -483,9 +766,19 @@ impl Modifier {
         (*self & Self::CTRL) == Self::CTRL
     }
 
+    /// Returns whether the alt key is included in this set.
+    pub fn alt(&self) -> bool {
+        self.contains(Self::ALT)
+    }
+
+    /// Returns whether the logo key is included in this set.
+    pub fn logo(&self) -> bool {
+        self.contains(Self::LOGO)
+    }
+
     /// Returns the cardinality of this set.
     pub fn card(&self) -> usize {
-        self.shift() as usize + self.ctrl() as usize
+        self.0.count_ones() as usize
     }
 }
 
@@ -513,11 +806,10 @@ impl Serialize for Modifier {
         S: serde::Serializer,
     {
         let mut seq = serializer.serialize_seq(Some(self.card()))?;
-        if self.shift() {
-            seq.serialize_element(Self::SHIFT_STR)?;
-        }
-        if self.ctrl() {
-            seq.serialize_element(Self::CTRL_STR)?;
+        for (modifier, name) in Self::NAMED {
+            if self.contains(modifier) {
+                seq.serialize_element(name)?;
+            }
         }
         seq.end()
     }
@@ -543,11 +835,9 @@ impl<'de> Deserialize<'de> for Modifier {
             {
                 let mut modifier = Modifier::NONE;
                 while let Some(element) = seq.next_element::<&str>()? {
-                    match element {
-                        Modifier::SHIFT_STR => modifier = modifier | Modifier::SHIFT,
-                        Modifier::CTRL_STR => modifier = modifier | Modifier::CTRL,
-                        _ => return Err(serde::de::Error::custom("invalid modifier")),
-                    }
+                    modifier = modifier
+                        | Modifier::from_name(element)
+                            .ok_or_else(|| serde::de::Error::custom("invalid modifier"))?;
                 }
                 Ok(modifier)
             }
@@ -0,0 +1,438 @@
+//! Rebindable keyboard shortcuts.
+//!
+//! A [`Keymap`] maps app-defined actions to one or more [`KeySequence`]s, which are made up of one
+//! or more [`Binding`]s pressed one after another (chords), eg. `Ctrl+K Ctrl+S`.
+//!
+//! Keymaps are serializable, so they're meant to be stored in the app's config:
+//!
+//! ```ignore
+//! #[derive(Clone, PartialEq, Serialize, Deserialize)]
+//! enum Action {
+//!     Save,
+//!     SaveAll,
+//! }
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Config {
+//!     keymap: Keymap<Action>,
+//!     // ...
+//! }
+//!
+//! impl Default for Config {
+//!     fn default() -> Self {
+//!         Self {
+//!             keymap: Keymap::new()
+//!                 .bind(Action::Save, "Ctrl+S".parse().unwrap())
+//!                 .bind(Action::SaveAll, "Ctrl+K Ctrl+S".parse().unwrap()),
+//!             // ...
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Keymaps loaded from config files replace the default keymap as a whole, so actions added in
+//! newer versions of the app should be added back in with [`Keymap::merge_defaults`].
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Serialize};
+
+use crate::input::{Input, Modifier, Scope, VirtualKeyCode};
+
+/// The maximum number of bindings in a key sequence.
+pub(crate) const MAX_SEQUENCE_LENGTH: usize = 4;
+
+/// A single key press, along with the modifier keys that must be held while pressing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub modifier: Modifier,
+    pub key: VirtualKeyCode,
+}
+
+impl Binding {
+    /// Creates a new binding.
+    pub fn new(modifier: Modifier, key: VirtualKeyCode) -> Self {
+        Self { modifier, key }
+    }
+}
+
+impl Display for Binding {
+    /// Formats the binding as eg. `Ctrl+Shift+S`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in Modifier::NAMED {
+            if self.modifier.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+impl FromStr for Binding {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifier = Modifier::NONE;
+        let mut parts = s.split('+').map(str::trim).peekable();
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                let deserializer: serde::de::value::StrDeserializer<serde::de::value::Error> =
+                    part.into_deserializer();
+                let key = VirtualKeyCode::deserialize(deserializer)
+                    .map_err(|_| KeymapError::InvalidKey(part.to_owned()))?;
+                return Ok(Self { modifier, key });
+            }
+            modifier = modifier
+                | Modifier::from_name(part)
+                    .ok_or_else(|| KeymapError::InvalidModifier(part.to_owned()))?;
+        }
+        Err(KeymapError::Empty)
+    }
+}
+
+/// A sequence of bindings that need to be pressed one after another, eg. `Ctrl+K Ctrl+S`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(Vec<Binding>);
+
+impl KeySequence {
+    /// Returns the bindings in the sequence.
+    pub fn bindings(&self) -> &[Binding] {
+        &self.0
+    }
+
+    /// Returns whether this sequence starts with the other sequence.
+    pub fn starts_with(&self, other: &KeySequence) -> bool {
+        self.0.starts_with(&other.0)
+    }
+
+    /// Returns whether this sequence ends with the other sequence.
+    pub fn ends_with(&self, other: &KeySequence) -> bool {
+        self.0.ends_with(&other.0)
+    }
+
+    /// Returns whether the other sequence appears in this sequence anywhere other than at its
+    /// end, ie. whether it's completed while typing this sequence.
+    fn completes_within(&self, other: &KeySequence) -> bool {
+        let length = other.0.len();
+        length < self.0.len()
+            && self.0[..self.0.len() - 1]
+                .windows(length)
+                .any(|w| w == other.0)
+    }
+}
+
+impl From<Binding> for KeySequence {
+    fn from(binding: Binding) -> Self {
+        Self(vec![binding])
+    }
+}
+
+impl Display for KeySequence {
+    /// Formats the sequence as space-separated bindings.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, binding) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{binding}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bindings = s
+            .split_whitespace()
+            .map(Binding::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if bindings.is_empty() {
+            return Err(KeymapError::Empty);
+        }
+        if bindings.len() > MAX_SEQUENCE_LENGTH {
+            return Err(KeymapError::TooLong);
+        }
+        Ok(Self(bindings))
+    }
+}
+
+impl Serialize for KeySequence {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// An error while parsing a key sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapError {
+    Empty,
+    TooLong,
+    InvalidKey(String),
+    InvalidModifier(String),
+}
+
+impl Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "key sequence is empty"),
+            Self::TooLong => write!(
+                f,
+                "key sequence is longer than {MAX_SEQUENCE_LENGTH} bindings"
+            ),
+            Self::InvalidKey(key) => write!(f, "invalid key {key:?}"),
+            Self::InvalidModifier(modifier) => write!(f, "invalid modifier {modifier:?}"),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// An action along with all the key sequences bound to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeymapEntry<A> {
+    action: A,
+    bindings: Vec<KeySequence>,
+}
+
+/// The way two key sequences conflict with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The sequences are the same, so both actions are triggered at once.
+    Identical,
+    /// The shorter sequence appears inside of the longer one before its last binding, eg.
+    /// `Ctrl+K` and `Ctrl+K Ctrl+S`. The shorter sequence's action is triggered partway through
+    /// typing the longer one, and then the longer sequence's action is triggered too.
+    Interrupts,
+    /// The longer sequence ends with the shorter one, eg. `Ctrl+S` and `Ctrl+K Ctrl+S`. Only the
+    /// longer sequence's action is triggered when it's typed, so the shorter sequence's action
+    /// cannot be triggered right after the rest of the longer sequence.
+    Shadows,
+}
+
+/// Two key sequences that interfere with each other. `first` is never longer than `second`.
+#[derive(Debug)]
+pub struct Conflict<'k, A> {
+    pub first: (&'k A, &'k KeySequence),
+    pub second: (&'k A, &'k KeySequence),
+    pub kind: ConflictKind,
+}
+
+/// A mapping from actions to key sequences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Keymap<A> {
+    entries: Vec<KeymapEntry<A>>,
+}
+
+impl<A> Keymap<A>
+where
+    A: PartialEq,
+{
+    /// Creates a new, empty keymap.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    fn entry_mut(&mut self, action: A) -> &mut KeymapEntry<A> {
+        match self.entries.iter().position(|entry| entry.action == action) {
+            Some(index) => &mut self.entries[index],
+            None => {
+                self.entries.push(KeymapEntry {
+                    action,
+                    bindings: Vec::new(),
+                });
+                self.entries.last_mut().unwrap()
+            }
+        }
+    }
+
+    /// Adds a key sequence to the action. This is meant for building the default keymap.
+    pub fn bind(mut self, action: A, sequence: KeySequence) -> Self {
+        self.entry_mut(action).bindings.push(sequence);
+        self
+    }
+
+    /// Returns the key sequences bound to the action.
+    pub fn bindings(&self, action: &A) -> &[KeySequence] {
+        self.entries
+            .iter()
+            .find(|entry| entry.action == *action)
+            .map(|entry| &entry.bindings[..])
+            .unwrap_or(&[])
+    }
+
+    /// Replaces the key sequences bound to the action.
+    pub fn set_bindings(&mut self, action: A, bindings: Vec<KeySequence>) {
+        self.entry_mut(action).bindings = bindings;
+    }
+
+    /// Adds actions from the default keymap which are missing from this keymap.
+    ///
+    /// Actions that are present but have no bindings are kept unbound.
+    pub fn merge_defaults(&mut self, defaults: Keymap<A>) {
+        for entry in defaults.entries {
+            if !self
+                .entries
+                .iter()
+                .any(|other| other.action == entry.action)
+            {
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    fn sequences(&self) -> impl Iterator<Item = (&A, &KeySequence)> {
+        self.entries.iter().flat_map(|entry| {
+            entry
+                .bindings
+                .iter()
+                .map(move |sequence| (&entry.action, sequence))
+        })
+    }
+
+    /// Returns all pairs of conflicting key sequences in the keymap.
+    ///
+    /// A conflict occurs when two sequences are the same, when a shorter sequence is completed
+    /// while typing a longer one, or when a longer sequence ends with a shorter one. See
+    /// [`ConflictKind`] for how each of these behaves.
+    pub fn conflicts(&self) -> Vec<Conflict<'_, A>> {
+        let sequences: Vec<_> = self.sequences().collect();
+        let mut conflicts = Vec::new();
+        for (i, &a) in sequences.iter().enumerate() {
+            for &b in &sequences[i + 1..] {
+                let (first, second) = if b.1.bindings().len() < a.1.bindings().len() {
+                    (b, a)
+                } else {
+                    (a, b)
+                };
+                let kind = if first.1 == second.1 {
+                    ConflictKind::Identical
+                } else if second.1.completes_within(first.1) {
+                    ConflictKind::Interrupts
+                } else if second.1.ends_with(first.1) {
+                    ConflictKind::Shadows
+                } else {
+                    continue;
+                };
+                conflicts.push(Conflict {
+                    first,
+                    second,
+                    kind,
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// Returns the longest key sequences which were completed during this frame.
+    fn completed_sequences<'k>(&'k self, input: &Input) -> Vec<(&'k A, &'k KeySequence)> {
+        let mut longest = 0;
+        let mut completed = Vec::new();
+        for (action, sequence) in self.sequences() {
+            let length = sequence.bindings().len();
+            if length >= longest && input.key_sequence_just_completed(sequence) {
+                if length > longest {
+                    completed.clear();
+                    longest = length;
+                }
+                completed.push((action, sequence));
+            }
+        }
+        completed
+    }
+
+    /// Returns whether the action was triggered during this frame, in the global scope.
+    ///
+    /// An action is triggered when its sequence is the most recently typed bindings. If a longer
+    /// key sequence ends with a shorter one, eg. `Ctrl+K Ctrl+S` and `Ctrl+S`, only the action
+    /// bound to the longer sequence is triggered. Sequences which are completed partway through
+    /// typing a longer sequence, eg. `Ctrl+K` in `Ctrl+K Ctrl+S`, are triggered as soon as they're
+    /// completed. [`conflicts`][Self::conflicts] reports both of these cases.
+    pub fn triggered(&self, input: &Input, action: &A) -> bool {
+        self.triggered_in(input, Scope::Global, action)
+    }
+
+    /// Returns whether the action was triggered during this frame, in the given scope.
+    ///
+    /// The action is not triggered if the last key of its sequence was consumed by a scope with
+    /// a higher priority; see [`Input::key_available`].
+    pub fn triggered_in(&self, input: &Input, scope: Scope, action: &A) -> bool {
+        self.completed_sequences(input)
+            .into_iter()
+            .any(|(other, sequence)| {
+                other == action
+                    && sequence
+                        .bindings()
+                        .last()
+                        .map(|binding| input.key_available(scope, binding.key))
+                        .unwrap_or(false)
+            })
+    }
+}
+
+impl<A> Default for Keymap<A>
+where
+    A: PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(keymap: &Keymap<&'static str>) -> Vec<(&'static str, &'static str, ConflictKind)> {
+        keymap
+            .conflicts()
+            .into_iter()
+            .map(|conflict| (*conflict.first.0, *conflict.second.0, conflict.kind))
+            .collect()
+    }
+
+    #[test]
+    fn conflicts_match_trigger_semantics() {
+        let keymap = Keymap::new()
+            .bind("open", "Ctrl+K".parse().unwrap())
+            .bind("save", "Ctrl+S".parse().unwrap())
+            .bind("save-all", "Ctrl+K Ctrl+S".parse().unwrap())
+            .bind("quit", "Ctrl+Q".parse().unwrap())
+            .bind("exit", "Ctrl+Q".parse().unwrap())
+            .bind("select", "Alt+S".parse().unwrap());
+        assert_eq!(
+            kinds(&keymap),
+            [
+                ("open", "save-all", ConflictKind::Interrupts),
+                ("save", "save-all", ConflictKind::Shadows),
+                ("quit", "exit", ConflictKind::Identical),
+            ]
+        );
+    }
+
+    #[test]
+    fn modifiers_round_trip() {
+        let sequence: KeySequence = "Ctrl+Alt+Shift+Logo+S Alt+F4".parse().unwrap();
+        assert_eq!(sequence.to_string(), "Ctrl+Alt+Shift+Logo+S Alt+F4");
+        assert_eq!(sequence.bindings()[1].modifier, Modifier::ALT);
+    }
+}
@@ -1,11 +1,15 @@
 pub extern crate paws;
 
+mod accessibility;
 mod input;
+mod keymap;
 mod render;
 
 use std::ops::{Deref, DerefMut};
 
+pub use accessibility::*;
 pub use input::*;
+pub use keymap::*;
 pub use render::*;
 
 /// paws UI state specialized to the selected backend, and extended with input capabilities.
@@ -8,18 +8,20 @@ use std::rc::Rc;
 use arrayvec::ArrayString;
 use mau_i18n::from_language::FromLanguage;
 use mau_i18n::translate_enum::TranslateEnum;
-use mau_i18n::{Formatted, Language};
+use mau_i18n::{strip_isolation_marks, Direction, Formatted, Language};
+use mau_renderer::{RenderBackend, TextDirection};
 use mau_ui::winit::event::{Event, WindowEvent};
 use mau_ui::winit::event_loop::ControlFlow;
 use mau_ui::winit::window::CursorIcon;
-use mau_ui::{Input, UiRenderFrame};
+use mau_ui::{AccessibilityAdapter, AccessibilityTree, Input, UiRenderFrame};
 use native_dialog::{MessageDialog, MessageType};
-use paws::{vector, Layout};
+use paws::{point, vector, Layout, Rect};
 
-use crate::clipboard;
-use crate::config::{AppConfig, WindowConfig};
+use crate::clipboard::{self, Clipboard, ClipboardBackend, PasteId, PasteResult};
+use crate::config::{AppConfig, InvalidField, Overrides, WindowConfig};
 use crate::error::Error;
 use crate::i18n::{LanguageMap, LanguageMapInit};
+use crate::storage::Storage;
 use mau_ui::winit::{
     dpi::{PhysicalPosition, PhysicalSize},
     event_loop::EventLoop,
@@ -56,6 +58,13 @@ where
     pub ui: &'a mut Ui,
     pub input: &'a mut Input,
     pub config: &'a mut T::Config,
+    /// The app's data and cache directories.
+    pub storage: &'a Storage,
+    /// The clipboard.
+    pub clipboard: &'a Clipboard,
+    /// The accessibility tree for the current frame. Widgets should add nodes describing
+    /// themselves to this tree as they're processed.
+    pub accessibility: &'a mut AccessibilityTree,
 }
 
 /// Trait implemented by all app states.
@@ -71,6 +80,16 @@ where
     /// _processing_ in the codebase.
     fn process(&mut self, args: AppContext<T>) -> Result<(), Self::Error>;
 
+    /// Called once before the first frame is processed, if any fields in the config were invalid
+    /// and had to be reset to their default values.
+    ///
+    /// This can be used to let the user know their settings were changed.
+    fn config_repaired(&mut self, _fields: &[InvalidField]) {}
+
+    /// Called before processing a frame for each paste started with
+    /// [`Clipboard::paste_async`] that has finished since the last frame.
+    fn clipboard_pasted(&mut self, _paste: PasteId, _result: PasteResult) {}
+
     /// Returns the next state after this one.
     ///
     /// If no state transitions should occur, this should simply return `self`. Otherwise, another
@@ -91,17 +110,21 @@ where
             .load_language("en-US")
             .expect("English language must be present")
     });
+    // Neither the log nor the native dialog are drawn by the app's renderer, so they can't be
+    // trusted to handle isolation marks.
     let _ = write!(
         message,
         "{}",
-        Formatted::new(language.clone(), "failure")
-            .format()
-            .with("message", error.translate(&language))
-            .done(),
+        strip_isolation_marks(
+            &Formatted::new(language.clone(), "failure")
+                .format()
+                .with("message", error.translate(&language))
+                .done()
+        ),
     );
     log::error!(
         "inner_main() returned with an Err:\n{}",
-        error.translate(&language)
+        strip_isolation_marks(&error.translate(&language))
     );
     MessageDialog::new()
         .set_title("NetCanv - Error")
@@ -122,6 +145,27 @@ pub struct App {
     ///
     /// **Default:** `None`
     pub minimum_window_size: Option<(u32, u32)>,
+
+    /// The adapter the accessibility tree is handed over to at the end of each frame.
+    ///
+    /// **Default:** `None`
+    pub accessibility_adapter: Option<Box<dyn AccessibilityAdapter>>,
+
+    /// Explicit overrides of config values, eg. from the command line.
+    ///
+    /// **Default:** no overrides
+    pub config_overrides: Overrides,
+
+    /// The config profile to load, or `None` for the default profile.
+    ///
+    /// **Default:** `None`
+    pub config_profile: Option<String>,
+
+    /// The clipboard backend to use.
+    ///
+    /// **Default:** `None`, which means the system clipboard is used, or an in-memory clipboard if
+    /// the system clipboard isn't available.
+    pub clipboard_backend: Option<Box<dyn ClipboardBackend>>,
 }
 
 impl App {
@@ -142,6 +186,38 @@ impl App {
         self
     }
 
+    /// Sets the config overrides. These take precedence over all other config sources, and are
+    /// never saved to the user's config file.
+    ///
+    /// [`Overrides::from_args`] can be used to let the user override config values using
+    /// command line arguments.
+    pub fn config_overrides(mut self, overrides: Overrides) -> Self {
+        self.config_overrides = overrides;
+        self
+    }
+
+    /// Sets the config profile to load instead of the default profile.
+    ///
+    /// [`profile_from_args`][crate::config::profile_from_args] can be used to let the user pick
+    /// the profile using command line arguments.
+    pub fn config_profile(mut self, profile: Option<String>) -> Self {
+        self.config_profile = profile;
+        self
+    }
+
+    /// Sets the clipboard backend, eg. a [`MemoryClipboard`][crate::clipboard::MemoryClipboard]
+    /// to test copy and paste without a display server.
+    pub fn clipboard_backend(mut self, backend: impl ClipboardBackend + 'static) -> Self {
+        self.clipboard_backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Sets the adapter used for exposing the UI to assistive technology such as screen readers.
+    pub fn accessibility_adapter(mut self, adapter: impl AccessibilityAdapter + 'static) -> Self {
+        self.accessibility_adapter = Some(Box::new(adapter));
+        self
+    }
+
     /// Sets the initial state of the app.
     ///
     /// This must be the last function called on this builder, after which `run()` can be called
@@ -162,6 +238,10 @@ impl Default for App {
         Self {
             default_window_size: (1024, 600),
             minimum_window_size: None,
+            accessibility_adapter: None,
+            config_overrides: Overrides::new(),
+            config_profile: None,
+            clipboard_backend: None,
         }
     }
 }
@@ -189,7 +269,10 @@ where
         S: AppState<T> + 'static,
     {
         log::debug!("loading config");
-        let mut config = T::Config::load_or_create()?;
+        T::Config::select_profile(self.app.config_profile.as_deref())?;
+        let (mut config, repaired_fields) = T::Config::load_and_repair(&self.app.config_overrides)?;
+
+        let storage = Storage::new(T::Config::app_name())?;
 
         log::debug!("loading language map");
         let langmap = T::LanguageMap::new();
@@ -230,23 +313,31 @@ where
 
         let mut ui = Ui::new(renderer);
         let mut input = Input::new();
+        let mut accessibility = AccessibilityTree::new();
+        let mut accessibility_adapter = self.app.accessibility_adapter;
 
-        let init_state = match (self.init_state)() {
+        let mut init_state = match (self.init_state)() {
             Ok(state) => state,
             Err(error) => {
                 report_error(error, &langmap, language.borrow().clone());
                 return Ok(());
             }
         };
+        if !repaired_fields.is_empty() {
+            init_state.config_repaired(&repaired_fields);
+        }
         let mut state: Option<Box<dyn AppState<T, Error = S::Error>>> = Some(Box::new(init_state));
 
         // Initialize the clipboard because we now have a window handle.
-        match clipboard::init() {
-            Ok(_) => (),
-            Err(error) => {
+        let clipboard = match self.app.clipboard_backend {
+            Some(backend) => Clipboard::from_boxed(backend),
+            None => Clipboard::system().unwrap_or_else(|error| {
                 log::error!("failed to initialize clipboard: {:?}", error);
-            }
-        }
+                log::warn!("falling back to an in-memory clipboard");
+                Clipboard::in_memory()
+            }),
+        };
+        clipboard::install(clipboard.clone());
 
         log::debug!("init done! starting event loop");
 
@@ -289,6 +380,11 @@ where
 
                 Event::MainEventsCleared => {
                     let window_size = ui.window().inner_size();
+                    let text_direction = match language.borrow().as_ref().map(Language::direction) {
+                        Some(Direction::RightToLeft) => TextDirection::RightToLeft,
+                        _ => TextDirection::LeftToRight,
+                    };
+                    ui.render().set_text_direction(text_direction);
                     if let Err(error) = ui.render_frame(|ui| {
                         ui.root(
                             vector(window_size.width as f32, window_size.height as f32),
@@ -298,6 +394,21 @@ where
                         // view::layout::full_screen(&mut root_view);
 
                         input.set_cursor(CursorIcon::Default);
+                        for (paste, result) in clipboard.finished_pastes() {
+                            state.as_mut().unwrap().clipboard_pasted(paste, result);
+                        }
+                        let requests = accessibility_adapter
+                            .as_mut()
+                            .map(|adapter| adapter.take_requests())
+                            .unwrap_or_default();
+                        accessibility.begin_frame(
+                            T::pretty_name(),
+                            Rect::new(
+                                point(0.0, 0.0),
+                                vector(window_size.width as f32, window_size.height as f32),
+                            ),
+                            requests,
+                        );
                         let result = state
                             .as_mut()
                             .unwrap()
@@ -305,8 +416,15 @@ where
                                 ui,
                                 input: &mut input,
                                 config: &mut config,
+                                storage: &storage,
+                                clipboard: &clipboard,
+                                accessibility: &mut accessibility,
                             })
                             .and_then(|()| state.take().unwrap().next_state(ui.render()));
+                        accessibility.finish_frame();
+                        if let Some(adapter) = &mut accessibility_adapter {
+                            adapter.update(&accessibility);
+                        }
                         match result {
                             Ok(next_state) => state = Some(next_state),
                             Err(error) => {
@@ -318,6 +436,7 @@ where
                         log::error!("render error: {}", error)
                     }
                     input.finish_frame(ui.window());
+                    config.save_if_idle();
                 }
 
                 Event::LoopDestroyed => {
@@ -335,6 +454,9 @@ where
                             maximized,
                         });
                     });
+                    if let Err(error) = config.flush() {
+                        log::error!("error while saving config: {error}");
+                    }
                 }
 
                 _ => (),
@@ -389,30 +511,9 @@ where
         let language = Rc::new(RefCell::new(None));
         match self.try_run_with_language(Rc::clone(&language)) {
             Ok(()) => (),
-            Err(payload) => {
-                log::error!("{payload}");
-                // let mut message = String::new();
-                // let language = language.unwrap_or_else(|| {
-                //     Assets::load_language(Some("en-US")).expect("English language must be present")
-                // });
-                // let _ = write!(
-                //     message,
-                //     "{}",
-                //     Formatted::new(language.clone(), "failure")
-                //         .format()
-                //         .with("message", payload.translate(&language))
-                //         .done(),
-                // );
-                // log::error!(
-                //     "inner_main() returned with an Err:\n{}",
-                //     payload.translate(&language)
-                // );
-                // MessageDialog::new()
-                //     .set_title("NetCanv - Error")
-                //     .set_text(&message)
-                //     .set_type(MessageType::Error)
-                //     .show_alert()
-                //     .unwrap();
+            Err(error) => {
+                let langmap = T::LanguageMap::new();
+                report_error(error, &langmap, language.borrow().clone());
             }
         }
     }
@@ -1,97 +1,415 @@
 //! Platform-agnostic clipboard handling.
+//!
+//! Besides plain text and images, the clipboard can hold HTML, lists of files, and data in custom
+//! formats identified by MIME types. Several representations of the same data can be copied at
+//! once using [`ClipboardContent`], and the app can then pick the richest one it understands when
+//! pasting, by checking [`Clipboard::available_formats`].
+//!
+//! The clipboard is accessed through a [`ClipboardBackend`]. By default, apps use the
+//! [`SystemClipboard`], but they can choose a different backend using
+//! [`App::clipboard_backend`][crate::App::clipboard_backend], eg. a [`MemoryClipboard`] to test
+//! copy and paste without a display server.
+//!
+//! Pasting large content such as images can take a while, so it can be done on a worker thread
+//! using [`Clipboard::paste_async`]. The result is then delivered to the app state through
+//! [`AppState::clipboard_pasted`][crate::AppState::clipboard_pasted].
 
-use std::borrow::Cow;
-use std::sync::Mutex;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
-use arboard::{Clipboard, ImageData};
 use image::RgbaImage;
+use mau_ui::{Input, MouseButton};
 use once_cell::sync::Lazy;
 
 use crate::error::ClipboardError;
 
+mod memory;
+mod paste;
+mod system;
+
+pub use memory::*;
+pub use paste::{ClipboardData, PasteHandle, PasteId, PasteResult};
+pub use system::*;
+
+use paste::PasteQueue;
+
+/// The clipboard used by the free functions in this module.
 static CLIPBOARD: Lazy<Mutex<Option<Clipboard>>> = Lazy::new(|| Mutex::new(None));
 
-/// Initializes the clipboard in a platform-specific way.
-#[allow(unused)]
+/// A format clipboard data can be stored in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClipboardFormat {
+    /// Data in an app-specific format, identified by a MIME type such as
+    /// `application/x-myapp-shapes`.
+    Custom(String),
+    /// An HTML fragment.
+    Html,
+    /// A list of paths to files.
+    Files,
+    /// An RGBA image.
+    Image,
+    /// Plain text.
+    Text,
+}
+
+impl fmt::Display for ClipboardFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Custom(mime) => write!(f, "{mime}"),
+            Self::Html => write!(f, "HTML"),
+            Self::Files => write!(f, "files"),
+            Self::Image => write!(f, "an image"),
+            Self::Text => write!(f, "text"),
+        }
+    }
+}
+
+/// Several representations of the same data, copied to the clipboard at once.
+///
+/// Apps should generally provide a plain text representation alongside any richer ones, so that
+/// the data can also be pasted into apps that don't understand them.
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardContent {
+    text: Option<String>,
+    html: Option<String>,
+    files: Vec<PathBuf>,
+    image: Option<RgbaImage>,
+    custom: Vec<(String, Vec<u8>)>,
+}
+
+impl ClipboardContent {
+    /// Creates empty clipboard content.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a plain text representation.
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Adds an HTML representation.
+    pub fn with_html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    /// Adds a list of files.
+    pub fn with_files(mut self, files: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.files = files.into_iter().collect();
+        self
+    }
+
+    /// Adds an image representation.
+    pub fn with_image(mut self, image: RgbaImage) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Adds a representation in a custom format, identified by the given MIME type.
+    pub fn with_custom(mut self, mime: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        let mime = mime.into();
+        self.custom.retain(|(other, _)| *other != mime);
+        self.custom.push((mime, data.into()));
+        self
+    }
+
+    /// Returns the formats the content is available in, richest first.
+    pub fn formats(&self) -> Vec<ClipboardFormat> {
+        let mut formats: Vec<_> = self
+            .custom
+            .iter()
+            .map(|(mime, _)| ClipboardFormat::Custom(mime.clone()))
+            .collect();
+        if self.html.is_some() {
+            formats.push(ClipboardFormat::Html);
+        }
+        if !self.files.is_empty() {
+            formats.push(ClipboardFormat::Files);
+        }
+        if self.image.is_some() {
+            formats.push(ClipboardFormat::Image);
+        }
+        if self.text.is_some() || !self.files.is_empty() {
+            formats.push(ClipboardFormat::Text);
+        }
+        formats
+    }
+}
+
+/// A source and destination of clipboard data.
+pub trait ClipboardBackend: Send {
+    /// Copies several representations of the same data into the clipboard, replacing what was
+    /// previously there.
+    fn copy(&mut self, content: ClipboardContent) -> Result<(), ClipboardError>;
+
+    /// Returns the formats the clipboard's content is available in, richest first.
+    fn available_formats(&mut self) -> Result<Vec<ClipboardFormat>, ClipboardError>;
+
+    /// Pastes the contents of the clipboard into a string.
+    fn paste_string(&mut self) -> Result<String, ClipboardError>;
+
+    /// Pastes HTML from the clipboard.
+    fn paste_html(&mut self) -> Result<String, ClipboardError>;
+
+    /// Pastes a list of files from the clipboard.
+    fn paste_files(&mut self) -> Result<Vec<PathBuf>, ClipboardError>;
+
+    /// Pastes data in the custom format identified by the given MIME type from the clipboard.
+    fn paste_custom(&mut self, mime: &str) -> Result<Vec<u8>, ClipboardError>;
+
+    /// Pastes an image from the clipboard.
+    fn paste_image(&mut self) -> Result<RgbaImage, ClipboardError>;
+
+    /// Returns whether the backend has a primary selection, separate from the regular clipboard.
+    fn has_primary_selection(&self) -> bool {
+        false
+    }
+
+    /// Sets the text of the primary selection. Does nothing if the backend doesn't have a primary
+    /// selection.
+    fn set_primary_selection(&mut self, _text: String) -> Result<(), ClipboardError> {
+        Ok(())
+    }
+
+    /// Returns the text of the primary selection, or `None` if the primary selection is empty or
+    /// the backend doesn't have one.
+    fn primary_selection(&mut self) -> Result<Option<String>, ClipboardError> {
+        Ok(None)
+    }
+}
+
+/// A handle to the clipboard backend used by the app.
+///
+/// Handles are cheap to clone, and all clones share the same backend.
+#[derive(Clone)]
+pub struct Clipboard {
+    backend: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+    pastes: Arc<PasteQueue>,
+}
+
+impl Clipboard {
+    /// Creates a clipboard that uses the given backend.
+    pub fn new(backend: impl ClipboardBackend + 'static) -> Self {
+        Self::from_boxed(Box::new(backend))
+    }
+
+    /// Creates a clipboard that uses the given boxed backend.
+    pub fn from_boxed(backend: Box<dyn ClipboardBackend>) -> Self {
+        Self {
+            backend: Arc::new(Mutex::new(backend)),
+            pastes: Arc::new(PasteQueue::new()),
+        }
+    }
+
+    /// Creates a clipboard that uses the system clipboard.
+    pub fn system() -> Result<Self, ClipboardError> {
+        Ok(Self::new(SystemClipboard::new()?))
+    }
+
+    /// Creates a clipboard that keeps its content in memory.
+    pub fn in_memory() -> Self {
+        Self::new(MemoryClipboard::new())
+    }
+
+    fn with_backend<R>(&self, f: impl FnOnce(&mut dyn ClipboardBackend) -> R) -> R {
+        let mut backend = self.backend.lock().unwrap();
+        f(&mut **backend)
+    }
+
+    /// Copies several representations of the same data into the clipboard.
+    pub fn copy(&self, content: ClipboardContent) -> Result<(), ClipboardError> {
+        self.with_backend(|backend| backend.copy(content))
+    }
+
+    /// Copies the provided string into the clipboard.
+    pub fn copy_string(&self, string: String) -> Result<(), ClipboardError> {
+        self.copy(ClipboardContent::new().with_text(string))
+    }
+
+    /// Copies the provided image into the clipboard.
+    pub fn copy_image(&self, image: RgbaImage) -> Result<(), ClipboardError> {
+        self.copy(ClipboardContent::new().with_image(image))
+    }
+
+    /// Returns the formats the clipboard's content is available in, richest first.
+    pub fn available_formats(&self) -> Result<Vec<ClipboardFormat>, ClipboardError> {
+        self.with_backend(|backend| backend.available_formats())
+    }
+
+    /// Returns whether the clipboard's content is available in the given format.
+    pub fn has_format(&self, format: &ClipboardFormat) -> Result<bool, ClipboardError> {
+        Ok(self.available_formats()?.contains(format))
+    }
+
+    /// Pastes the contents of the clipboard into a string.
+    pub fn paste_string(&self) -> Result<String, ClipboardError> {
+        self.with_backend(|backend| backend.paste_string())
+    }
+
+    /// Pastes HTML from the clipboard.
+    pub fn paste_html(&self) -> Result<String, ClipboardError> {
+        self.with_backend(|backend| backend.paste_html())
+    }
+
+    /// Pastes a list of files from the clipboard.
+    pub fn paste_files(&self) -> Result<Vec<PathBuf>, ClipboardError> {
+        self.with_backend(|backend| backend.paste_files())
+    }
+
+    /// Pastes data in the custom format identified by the given MIME type from the clipboard.
+    pub fn paste_custom(&self, mime: &str) -> Result<Vec<u8>, ClipboardError> {
+        self.with_backend(|backend| backend.paste_custom(mime))
+    }
+
+    /// Pastes an image from the clipboard.
+    pub fn paste_image(&self) -> Result<RgbaImage, ClipboardError> {
+        self.with_backend(|backend| backend.paste_image())
+    }
+
+    /// Starts pasting in the given format on a worker thread.
+    ///
+    /// Once the paste finishes, its result is delivered to the app state through
+    /// [`AppState::clipboard_pasted`][crate::AppState::clipboard_pasted], along with the ID of the
+    /// returned handle. Pastes run one at a time on a single worker thread, and other clipboard
+    /// operations wait while it reads from the clipboard. Cancelling a paste through its handle
+    /// only drops the result; a read that has already started runs to completion.
+    pub fn paste_async(&self, format: ClipboardFormat) -> PasteHandle {
+        self.pastes.start(Arc::clone(&self.backend), format)
+    }
+
+    /// Returns the results of all pastes started with [`paste_async`][Self::paste_async] that
+    /// finished since the last call. Results of cancelled pastes are skipped.
+    ///
+    /// Apps don't need to call this, as the results are delivered to the app state
+    /// automatically.
+    pub fn finished_pastes(&self) -> Vec<(PasteId, PasteResult)> {
+        self.pastes.finished()
+    }
+
+    /// Returns whether the clipboard has a primary selection. This is only the case on Linux,
+    /// where selecting text makes it available for pasting with the middle mouse button.
+    pub fn has_primary_selection(&self) -> bool {
+        self.with_backend(|backend| backend.has_primary_selection())
+    }
+
+    /// Publishes selected text to the primary selection. Text widgets should call this whenever
+    /// their selection changes; publishing the same text again is cheap.
+    ///
+    /// Does nothing on platforms without a primary selection.
+    pub fn set_primary_selection(&self, text: &str) -> Result<(), ClipboardError> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.with_backend(|backend| backend.set_primary_selection(text.to_owned()))
+    }
+
+    /// Returns the text of the primary selection, or `None` if it's empty or there is no primary
+    /// selection on this platform.
+    pub fn primary_selection(&self) -> Result<Option<String>, ClipboardError> {
+        self.with_backend(|backend| backend.primary_selection())
+    }
+
+    /// Returns the text that should be pasted into a text widget if the middle mouse button was
+    /// just pressed over it, following the convention on Linux.
+    ///
+    /// Always returns `None` on platforms without a primary selection.
+    pub fn middle_click_paste(&self, input: &Input) -> Option<String> {
+        if !input.mouse_button_just_pressed(MouseButton::Middle) {
+            return None;
+        }
+        self.primary_selection().unwrap_or_else(|error| {
+            log::warn!("cannot read the primary selection: {}", error);
+            None
+        })
+    }
+}
+
+/// Initializes the clipboard used by the free functions in this module with the system
+/// clipboard.
 pub fn init() -> Result<(), ClipboardError> {
-    let mut clipboard = CLIPBOARD.lock().unwrap();
-    *clipboard = Some(Clipboard::new()?);
+    install(Clipboard::system()?);
     Ok(())
 }
 
+/// Sets the clipboard used by the free functions in this module.
+pub fn install(clipboard: Clipboard) {
+    *CLIPBOARD.lock().unwrap() = Some(clipboard);
+}
+
+/// Returns the clipboard used by the free functions in this module.
+pub fn global() -> Result<Clipboard, ClipboardError> {
+    CLIPBOARD
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or(ClipboardError::Uninitialized)
+}
+
+/// Copies several representations of the same data into the clipboard.
+pub fn copy(content: ClipboardContent) -> Result<(), ClipboardError> {
+    global()?.copy(content)
+}
+
+/// Returns the formats the clipboard's content is available in, richest first.
+pub fn available_formats() -> Result<Vec<ClipboardFormat>, ClipboardError> {
+    global()?.available_formats()
+}
+
+/// Returns whether the clipboard's content is available in the given format.
+pub fn has_format(format: &ClipboardFormat) -> Result<bool, ClipboardError> {
+    global()?.has_format(format)
+}
+
 /// Copies the provided string into the clipboard.
 pub fn copy_string(string: String) -> Result<(), ClipboardError> {
-    let mut clipboard = CLIPBOARD.lock().unwrap();
-    if let Some(clipboard) = &mut *clipboard {
-        clipboard
-            .set_text(string)
-            .map_err(|e| ClipboardError::SaveFailed {
-                error: e.to_string(),
-            })?;
-        Ok(())
-    } else {
-        Err(ClipboardError::Uninitialized)
-    }
+    global()?.copy_string(string)
 }
 
 /// Copies the provided image into the clipboard.
 pub fn copy_image(image: RgbaImage) -> Result<(), ClipboardError> {
-    let mut clipboard = CLIPBOARD.lock().unwrap();
-    if let Some(clipboard) = &mut *clipboard {
-        clipboard
-            .set_image(ImageData {
-                width: image.width() as usize,
-                height: image.height() as usize,
-                bytes: Cow::Borrowed(&image),
-            })
-            .map_err(|e| ClipboardError::SaveFailed {
-                error: e.to_string(),
-            })?;
-        Ok(())
-    } else {
-        Err(ClipboardError::Uninitialized)
-    }
+    global()?.copy_image(image)
 }
 
 /// Pastes the contents of the clipboard into a string.
 pub fn paste_string() -> Result<String, ClipboardError> {
-    let mut clipboard = CLIPBOARD.lock().unwrap();
-    if let Some(clipboard) = &mut *clipboard {
-        Ok(clipboard.get_text().map_err(|e| {
-            if let arboard::Error::ContentNotAvailable = e {
-                ClipboardError::DoesNotContainText
-            } else {
-                e.into()
-            }
-        })?)
-    } else {
-        Err(ClipboardError::Uninitialized)
-    }
+    global()?.paste_string()
+}
+
+/// Pastes HTML from the clipboard.
+pub fn paste_html() -> Result<String, ClipboardError> {
+    global()?.paste_html()
+}
+
+/// Pastes a list of files from the clipboard.
+pub fn paste_files() -> Result<Vec<PathBuf>, ClipboardError> {
+    global()?.paste_files()
+}
+
+/// Pastes data in the custom format identified by the given MIME type from the clipboard.
+pub fn paste_custom(mime: &str) -> Result<Vec<u8>, ClipboardError> {
+    global()?.paste_custom(mime)
 }
 
+/// Pastes an image from the clipboard.
 pub fn paste_image() -> Result<RgbaImage, ClipboardError> {
-    let mut clipboard = CLIPBOARD.lock().unwrap();
-    if let Some(clipboard) = &mut *clipboard {
-        let image = clipboard
-            .get_image()
-            .map_err(|e| {
-                if let arboard::Error::ContentNotAvailable = e {
-                    ClipboardError::DoesNotContainAnImage
-                } else {
-                    e.into()
-                }
-            })?
-            .to_owned_img();
-        Ok(RgbaImage::from_vec(
-            image.width as u32,
-            image.height as u32,
-            match image.bytes {
-                Cow::Borrowed(_) => unreachable!("clipboard data must be owned at this point"),
-                Cow::Owned(data) => data,
-            },
-        )
-        .expect("failed to create clipboard image"))
-    } else {
-        Err(ClipboardError::Uninitialized)
-    }
+    global()?.paste_image()
+}
+
+/// Publishes selected text to the primary selection.
+pub fn set_primary_selection(text: &str) -> Result<(), ClipboardError> {
+    global()?.set_primary_selection(text)
+}
+
+/// Returns the text of the primary selection.
+pub fn primary_selection() -> Result<Option<String>, ClipboardError> {
+    global()?.primary_selection()
+}
+
+/// Formats a list of files as plain text, one path per line.
+fn files_to_text(files: &[PathBuf]) -> String {
+    let paths: Vec<_> = files.iter().map(|path| path.to_string_lossy()).collect();
+    paths.join("\n")
 }
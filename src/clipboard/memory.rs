@@ -0,0 +1,113 @@
+//! A clipboard kept in memory, for testing and for platforms without a system clipboard.
+
+use std::path::PathBuf;
+
+use image::RgbaImage;
+
+use super::{files_to_text, ClipboardBackend, ClipboardContent, ClipboardFormat};
+use crate::error::ClipboardError;
+
+/// A clipboard that holds its content in memory, without talking to the system clipboard.
+///
+/// All formats are kept as-is, so this can be used for testing copy and paste on machines
+/// without a display server.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboard {
+    content: Option<ClipboardContent>,
+    primary_selection: Option<String>,
+}
+
+impl MemoryClipboard {
+    /// Creates an empty clipboard.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the content that was last copied.
+    pub fn content(&self) -> Option<&ClipboardContent> {
+        self.content.as_ref()
+    }
+
+    fn missing(format: ClipboardFormat) -> ClipboardError {
+        match format {
+            ClipboardFormat::Text => ClipboardError::DoesNotContainText,
+            ClipboardFormat::Image => ClipboardError::DoesNotContainAnImage,
+            format => ClipboardError::DoesNotContainFormat {
+                format: format.to_string(),
+            },
+        }
+    }
+}
+
+impl ClipboardBackend for MemoryClipboard {
+    fn copy(&mut self, content: ClipboardContent) -> Result<(), ClipboardError> {
+        self.content = Some(content);
+        Ok(())
+    }
+
+    fn available_formats(&mut self) -> Result<Vec<ClipboardFormat>, ClipboardError> {
+        Ok(self
+            .content
+            .as_ref()
+            .map(ClipboardContent::formats)
+            .unwrap_or_default())
+    }
+
+    fn paste_string(&mut self) -> Result<String, ClipboardError> {
+        let content = self.content.as_ref();
+        content
+            .and_then(|content| content.text.clone())
+            .or_else(|| {
+                content
+                    .filter(|content| !content.files.is_empty())
+                    .map(|content| files_to_text(&content.files))
+            })
+            .ok_or_else(|| Self::missing(ClipboardFormat::Text))
+    }
+
+    fn paste_html(&mut self) -> Result<String, ClipboardError> {
+        self.content
+            .as_ref()
+            .and_then(|content| content.html.clone())
+            .ok_or_else(|| Self::missing(ClipboardFormat::Html))
+    }
+
+    fn paste_files(&mut self) -> Result<Vec<PathBuf>, ClipboardError> {
+        self.content
+            .as_ref()
+            .map(|content| content.files.clone())
+            .filter(|files| !files.is_empty())
+            .ok_or_else(|| Self::missing(ClipboardFormat::Files))
+    }
+
+    fn paste_custom(&mut self, mime: &str) -> Result<Vec<u8>, ClipboardError> {
+        self.content
+            .as_ref()
+            .and_then(|content| content.custom.iter().find(|(other, _)| other == mime))
+            .map(|(_, data)| data.clone())
+            .ok_or_else(|| Self::missing(ClipboardFormat::Custom(mime.to_owned())))
+    }
+
+    fn paste_image(&mut self) -> Result<RgbaImage, ClipboardError> {
+        self.content
+            .as_ref()
+            .and_then(|content| content.image.clone())
+            .ok_or_else(|| Self::missing(ClipboardFormat::Image))
+    }
+
+    /// Mirrors the system clipboard, which only has a primary selection on Linux.
+    fn has_primary_selection(&self) -> bool {
+        cfg!(target_os = "linux")
+    }
+
+    fn set_primary_selection(&mut self, text: String) -> Result<(), ClipboardError> {
+        if self.has_primary_selection() {
+            self.primary_selection = Some(text);
+        }
+        Ok(())
+    }
+
+    fn primary_selection(&mut self) -> Result<Option<String>, ClipboardError> {
+        Ok(self.primary_selection.clone())
+    }
+}
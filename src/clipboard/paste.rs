@@ -0,0 +1,167 @@
+//! Pasting on a worker thread, so that large clipboard contents don't block the UI.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::{Arc, Mutex};
+
+use image::RgbaImage;
+
+use super::{ClipboardBackend, ClipboardFormat};
+use crate::error::ClipboardError;
+
+/// Identifies a paste started with [`Clipboard::paste_async`][super::Clipboard::paste_async].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PasteId(u64);
+
+/// Data pasted from the clipboard in one of the [`ClipboardFormat`]s.
+#[derive(Debug, Clone)]
+pub enum ClipboardData {
+    Custom(Vec<u8>),
+    Html(String),
+    Files(Vec<PathBuf>),
+    Image(RgbaImage),
+    Text(String),
+}
+
+/// The result of a paste running in the background.
+pub type PasteResult = Result<ClipboardData, ClipboardError>;
+
+/// A handle to a paste running in the background.
+///
+/// Dropping the handle does not cancel the paste.
+#[derive(Debug, Clone)]
+pub struct PasteHandle {
+    id: PasteId,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl PasteHandle {
+    /// Returns the ID the paste's result will be delivered with.
+    pub fn id(&self) -> PasteId {
+        self.id
+    }
+
+    /// Cancels the paste. Its result will not be delivered, even if it has already finished.
+    ///
+    /// This only drops the result: a paste that's already reading from the clipboard is not
+    /// interrupted. Pastes that haven't started yet are skipped.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the paste was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// A paste waiting for the worker thread.
+struct Job {
+    handle: PasteHandle,
+    backend: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+    format: ClipboardFormat,
+}
+
+/// Pastes that have been started, and the results of the ones that finished.
+///
+/// Pastes run one after another on a single worker thread, which is spawned by the first paste
+/// and exits together with the queue.
+pub(super) struct PasteQueue {
+    next_id: AtomicU64,
+    jobs: Mutex<Option<Sender<Job>>>,
+    sender: Mutex<Sender<(PasteHandle, PasteResult)>>,
+    receiver: Mutex<Receiver<(PasteHandle, PasteResult)>>,
+}
+
+impl PasteQueue {
+    pub(super) fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            next_id: AtomicU64::new(0),
+            jobs: Mutex::new(None),
+            sender: Mutex::new(sender),
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    /// Queues a paste in the given format on the worker thread.
+    pub(super) fn start(
+        &self,
+        backend: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+        format: ClipboardFormat,
+    ) -> PasteHandle {
+        let handle = PasteHandle {
+            id: PasteId(self.next_id.fetch_add(1, Ordering::Relaxed)),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        };
+        let job = Job {
+            handle: handle.clone(),
+            backend,
+            format,
+        };
+        let mut jobs = self.jobs.lock().unwrap();
+        let sent = jobs.get_or_insert_with(|| self.spawn_worker()).send(job);
+        // The worker only exits early if it panicked, eg. inside of the backend. Start a new one
+        // rather than losing this and all further pastes.
+        if let Err(SendError(job)) = sent {
+            log::error!("clipboard paste thread exited, restarting it");
+            let worker = jobs.insert(self.spawn_worker());
+            if let Err(SendError(job)) = worker.send(job) {
+                let error = ClipboardError::Unknown {
+                    error: "clipboard paste thread exited".into(),
+                };
+                let _ = self.sender.lock().unwrap().send((job.handle, Err(error)));
+            }
+        }
+        handle
+    }
+
+    fn spawn_worker(&self) -> Sender<Job> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let sender = self.sender.lock().unwrap().clone();
+        std::thread::Builder::new()
+            .name("clipboard paste".into())
+            .spawn(move || {
+                for job in queue {
+                    if job.handle.is_cancelled() {
+                        continue;
+                    }
+                    let result = paste(&job.backend, &job.format);
+                    // The receiving end only goes away together with the clipboard, at which
+                    // point nobody is interested in the result anymore.
+                    let _ = sender.send((job.handle, result));
+                }
+            })
+            .expect("cannot spawn clipboard paste thread");
+        jobs
+    }
+
+    /// Returns the results of all pastes that finished since the last call, skipping cancelled
+    /// ones.
+    pub(super) fn finished(&self) -> Vec<(PasteId, PasteResult)> {
+        self.receiver
+            .lock()
+            .unwrap()
+            .try_iter()
+            .filter(|(handle, _)| !handle.is_cancelled())
+            .map(|(handle, result)| (handle.id, result))
+            .collect()
+    }
+}
+
+/// Pastes data in the given format. The backend is locked only for the read itself, so other
+/// clipboard calls wait for at most one paste.
+fn paste(backend: &Mutex<Box<dyn ClipboardBackend>>, format: &ClipboardFormat) -> PasteResult {
+    // A backend that panicked while pasting may be in any state, so it's not used anymore.
+    let mut backend = backend.lock().map_err(|_| ClipboardError::Unknown {
+        error: "the clipboard backend panicked".into(),
+    })?;
+    Ok(match format {
+        ClipboardFormat::Custom(mime) => ClipboardData::Custom(backend.paste_custom(mime)?),
+        ClipboardFormat::Html => ClipboardData::Html(backend.paste_html()?),
+        ClipboardFormat::Files => ClipboardData::Files(backend.paste_files()?),
+        ClipboardFormat::Image => ClipboardData::Image(backend.paste_image()?),
+        ClipboardFormat::Text => ClipboardData::Text(backend.paste_string()?),
+    })
+}
@@ -0,0 +1,296 @@
+//! The system clipboard, accessed through `arboard`.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use arboard::ImageData;
+use image::RgbaImage;
+
+use super::{files_to_text, ClipboardBackend, ClipboardContent, ClipboardFormat};
+use crate::error::ClipboardError;
+
+/// The system clipboard.
+///
+/// The system clipboard only carries plain text and images, so any other formats are stashed in
+/// memory, along with a stamp of what was put on the system clipboard. When pasting, the stashed
+/// formats are only offered if the system clipboard still holds the same content, so they
+/// disappear as soon as anything else is copied. The stash is never written to disk, so other
+/// processes only see the plain text or image.
+pub struct SystemClipboard {
+    clipboard: arboard::Clipboard,
+    stash: Option<Stash>,
+    /// The text last published to the primary selection, so that publishing the same selection
+    /// every frame doesn't hit the display server.
+    #[cfg(target_os = "linux")]
+    primary_selection: Option<String>,
+}
+
+impl SystemClipboard {
+    /// Connects to the system clipboard.
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(Self {
+            clipboard: arboard::Clipboard::new()?,
+            stash: None,
+            #[cfg(target_os = "linux")]
+            primary_selection: None,
+        })
+    }
+
+    /// Returns the stash, if it belongs to what's currently on the system clipboard.
+    ///
+    /// If the stash was copied along with an image, this reads and hashes the whole image, so it
+    /// should only be used when actually pasting from the stash.
+    fn current_stash(&mut self) -> Option<&mut Stash> {
+        let current = match self.stash.as_ref()?.stamp {
+            Stamp::Text(_) => Stamp::Text(stamp(self.clipboard.get_text().ok()?.as_bytes())),
+            Stamp::Image(_) => Stamp::Image(stamp(&self.clipboard.get_image().ok()?.bytes)),
+        };
+        self.stash.as_mut().filter(|stash| stash.stamp == current)
+    }
+}
+
+impl ClipboardBackend for SystemClipboard {
+    /// If the content has an image, it's put on the system clipboard; otherwise the plain text
+    /// is.
+    fn copy(&mut self, content: ClipboardContent) -> Result<(), ClipboardError> {
+        // Whatever happens, the previous stash no longer belongs to the system clipboard.
+        self.stash = None;
+        let text = content
+            .text
+            .clone()
+            .or_else(|| (!content.files.is_empty()).then(|| files_to_text(&content.files)));
+        let stamp = if let Some(image) = &content.image {
+            self.clipboard
+                .set_image(ImageData {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes: Cow::Borrowed(image),
+                })
+                .map_err(|e| ClipboardError::SaveFailed {
+                    error: e.to_string(),
+                })?;
+            Stamp::Image(stamp(image))
+        } else if let Some(text) = &text {
+            self.clipboard
+                .set_text(text.clone())
+                .map_err(|e| ClipboardError::SaveFailed {
+                    error: e.to_string(),
+                })?;
+            Stamp::Text(stamp(text.as_bytes()))
+        } else {
+            return Err(ClipboardError::SaveFailed {
+                error: "no text or image to put on the clipboard".into(),
+            });
+        };
+
+        let needs_stash = content.html.is_some()
+            || !content.files.is_empty()
+            || !content.custom.is_empty()
+            || (content.image.is_some() && text.is_some());
+        if needs_stash {
+            self.stash = Some(Stash {
+                stamp,
+                text: content.image.is_some().then_some(text).flatten(),
+                html: content.html,
+                files: content.files,
+                custom: content.custom.into_iter().collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Only checks which formats are available, without verifying an image stash against the
+    /// system clipboard's image. If the image was replaced by another one, pasting the stashed
+    /// formats fails instead.
+    fn available_formats(&mut self) -> Result<Vec<ClipboardFormat>, ClipboardError> {
+        let text = self.clipboard.get_text().ok();
+        let stash = match &self.stash {
+            Some(stash) => match (stash.stamp, &text) {
+                (Stamp::Text(stashed), Some(text)) => stamp(text.as_bytes()) == stashed,
+                (Stamp::Image(_), None) => true,
+                _ => false,
+            }
+            .then_some(stash),
+            None => None,
+        };
+
+        let mut formats = Vec::new();
+        if let Some(stash) = stash {
+            formats.extend(stash.custom.keys().cloned().map(ClipboardFormat::Custom));
+            if stash.html.is_some() {
+                formats.push(ClipboardFormat::Html);
+            }
+        }
+        let has_files = stash.is_some_and(|stash| !stash.files.is_empty())
+            || text.as_deref().and_then(text_to_files).is_some();
+        if has_files {
+            formats.push(ClipboardFormat::Files);
+        }
+        // arboard can't list the formats on the clipboard, so an image has to be read to find out
+        // whether there is one.
+        let has_image = text.is_none() && (stash.is_some() || self.clipboard.get_image().is_ok());
+        if has_image {
+            formats.push(ClipboardFormat::Image);
+        }
+        if text.is_some() || stash.is_some_and(|stash| stash.text.is_some()) {
+            formats.push(ClipboardFormat::Text);
+        }
+        Ok(formats)
+    }
+
+    fn paste_string(&mut self) -> Result<String, ClipboardError> {
+        match self.clipboard.get_text() {
+            Ok(text) => Ok(text),
+            Err(arboard::Error::ContentNotAvailable) => self
+                .current_stash()
+                .and_then(|stash| stash.text.clone())
+                .ok_or(ClipboardError::DoesNotContainText),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn paste_html(&mut self) -> Result<String, ClipboardError> {
+        self.current_stash()
+            .and_then(|stash| stash.html.clone())
+            .ok_or(ClipboardError::DoesNotContainFormat {
+                format: ClipboardFormat::Html.to_string(),
+            })
+    }
+
+    fn paste_files(&mut self) -> Result<Vec<PathBuf>, ClipboardError> {
+        self.current_stash()
+            .map(|stash| stash.files.clone())
+            .filter(|files| !files.is_empty())
+            .or_else(|| {
+                self.clipboard
+                    .get_text()
+                    .ok()
+                    .as_deref()
+                    .and_then(text_to_files)
+            })
+            .ok_or(ClipboardError::DoesNotContainFormat {
+                format: ClipboardFormat::Files.to_string(),
+            })
+    }
+
+    fn paste_custom(&mut self, mime: &str) -> Result<Vec<u8>, ClipboardError> {
+        self.current_stash()
+            .and_then(|stash| stash.custom.get(mime).cloned())
+            .ok_or_else(|| ClipboardError::DoesNotContainFormat {
+                format: mime.to_owned(),
+            })
+    }
+
+    fn paste_image(&mut self) -> Result<RgbaImage, ClipboardError> {
+        let image = self.clipboard.get_image().map_err(|e| {
+            if let arboard::Error::ContentNotAvailable = e {
+                ClipboardError::DoesNotContainAnImage
+            } else {
+                e.into()
+            }
+        })?;
+        // The image data returned by arboard is already owned, so this doesn't copy it.
+        let (width, height) = (image.width as u32, image.height as u32);
+        RgbaImage::from_vec(width, height, image.bytes.into_owned())
+            .ok_or(ClipboardError::ConversionFailed)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn has_primary_selection(&self) -> bool {
+        true
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_primary_selection(&mut self, text: String) -> Result<(), ClipboardError> {
+        use arboard::{ClipboardExtLinux, LinuxClipboardKind};
+
+        if self.primary_selection.as_ref() == Some(&text) {
+            return Ok(());
+        }
+        self.clipboard
+            .set_text_with_clipboard(text.clone(), LinuxClipboardKind::Primary)
+            .map_err(|e| ClipboardError::SaveFailed {
+                error: e.to_string(),
+            })?;
+        self.primary_selection = Some(text);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    fn primary_selection(&mut self) -> Result<Option<String>, ClipboardError> {
+        use arboard::{ClipboardExtLinux, LinuxClipboardKind};
+
+        match self
+            .clipboard
+            .get_text_with_clipboard(LinuxClipboardKind::Primary)
+        {
+            Ok(text) => Ok(Some(text).filter(|text| !text.is_empty())),
+            Err(arboard::Error::ContentNotAvailable) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// The formats the system clipboard can't carry, stashed away until the next copy.
+#[derive(Debug)]
+struct Stash {
+    /// The stamp of the content put on the system clipboard along with the stashed formats.
+    stamp: Stamp,
+    text: Option<String>,
+    html: Option<String>,
+    files: Vec<PathBuf>,
+    custom: BTreeMap<String, Vec<u8>>,
+}
+
+/// Identifies the content put on the system clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stamp {
+    Text(u64),
+    Image(u64),
+}
+
+/// Computes a stamp identifying clipboard content, using the FNV-1a hash.
+fn stamp(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Parses a list of files from plain text, as put on the clipboard by file managers. Each line
+/// must either be a `file://` URI or an absolute path to an existing file.
+fn text_to_files(text: &str) -> Option<Vec<PathBuf>> {
+    let files: Option<Vec<_>> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            if let Some(path) = line.strip_prefix("file://") {
+                // Skip the host, which is empty for local files.
+                let path = &path[path.find('/')?..];
+                Some(PathBuf::from(percent_decode(path)?))
+            } else {
+                let path = Path::new(line);
+                (path.is_absolute() && path.exists()).then(|| path.to_owned())
+            }
+        })
+        .collect();
+    files.filter(|files| !files.is_empty())
+}
+
+/// Decodes `%XX` escapes in a URI path.
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
@@ -1,11 +1,50 @@
 //! Common configuration options.
 
-use std::path::PathBuf;
+mod format;
+pub(crate) mod layers;
+mod profiles;
+mod saving;
+mod validation;
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use directories::ProjectDirs;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::error::ConfigError;
+use layers::{Layers, LAYERS};
+
+pub use format::Format;
+pub use layers::{Layer, Overrides};
+pub use profiles::profile_from_args;
+pub use validation::{InvalidField, InvalidValue};
+
+/// The key under which the schema version is stored in config files.
+const VERSION_KEY: &str = "config_version";
+
+/// A config migration, which upgrades a config file from one schema version to the next.
+///
+/// Migrations operate on raw TOML rather than the config struct, so that they can still access
+/// keys that have since been renamed or removed.
+pub type Migration = fn(&mut toml::Value) -> Result<(), String>;
+
+/// Moves a config file that failed to deserialize out of the way, and returns the error that
+/// should be reported.
+fn broken_config(config_file: &Path, error: impl Display) -> ConfigError {
+    log::error!("error while deserializing config file: {}", error);
+    match crate::fs::move_aside(config_file, "broken") {
+        Ok(moved_to) => {
+            log::error!("the broken config file was moved to {:?}", moved_to);
+            ConfigError::Deserialize {
+                error: error.to_string(),
+                moved_to,
+            }
+        }
+        Err(error) => error.into(),
+    }
+}
 
 /// Window position and size.
 #[derive(Deserialize, Serialize)]
@@ -24,7 +63,53 @@ pub struct WindowConfig {
 ///
 /// mau automatically serializes/deserializes config files from the app directory upon the
 /// [`App`][crate::App]'s construction.
+///
+/// The required methods can be generated using `#[derive(AppConfig)]`, by annotating the struct
+/// with `#[app_name = "..."]`, the `String` field holding the language code with `#[language]`,
+/// and the `Option<WindowConfig>` field with `#[window]`. Provided items can be overridden with
+/// the `#[config(...)]` attribute:
+///
+/// ```ignore
+/// #[derive(Default, Deserialize, Serialize, AppConfig)]
+/// #[app_name = "my-app"]
+/// #[config(
+///     version = 1,
+///     migrations = "migrations",      // fn() -> &'static [Migration]
+///     validate = "Config::check",     // fn(&Config) -> Vec<InvalidField>
+///     format = "Json",                // a variant of `Format`
+///     shared_keys("window"),
+/// )]
+/// struct Config {
+///     #[language]
+///     language: String,
+///     #[window]
+///     window: Option<WindowConfig>,
+/// }
+/// ```
 pub trait AppConfig: DeserializeOwned + Serialize + Default {
+    /// The current version of the config's schema.
+    ///
+    /// This should be bumped whenever the config's structure changes in a way that isn't
+    /// backwards-compatible, and a migration from the previous version should be added to
+    /// [`migrations`][Self::migrations].
+    const VERSION: u32 = 0;
+
+    /// The format config files are stored in.
+    ///
+    /// When this is changed, existing config files in other formats are converted to the new
+    /// format the next time the config is loaded.
+    const FORMAT: Format = Format::Toml;
+
+    /// How long to wait after the last [`write`][Self::write] before saving the config.
+    const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+    /// Top-level keys whose values are shared between all [profiles][Self::select_profile].
+    ///
+    /// Shared values are always stored in the default profile, even while a named profile is
+    /// selected. For example, setting this to `&["window"]` makes the window's size and position
+    /// persist across profiles, instead of each profile remembering its own.
+    const SHARED_KEYS: &'static [&'static str] = &[];
+
     /// Returns the name of the app.
     ///
     /// This name is used to determine where to save config files.
@@ -39,63 +124,577 @@ pub trait AppConfig: DeserializeOwned + Serialize + Default {
     /// Returns a mutable reference to the window config.
     fn window_config_mut(&mut self) -> &mut Option<WindowConfig>;
 
+    /// Returns the list of migrations from older schema versions.
+    ///
+    /// The migration at index `n` upgrades the config from version `n` to version `n + 1`, so
+    /// there must be exactly [`VERSION`][Self::VERSION] migrations; otherwise loading the config
+    /// fails with [`ConfigError::MigrationCount`]. Config files saved before versioning was
+    /// introduced are treated as version 0.
+    fn migrations() -> &'static [Migration] {
+        &[]
+    }
+
+    /// Checks whether the config's values make sense.
+    ///
+    /// This is called after the config is loaded, and any fields returned are reset to their
+    /// default values. The app is then notified about the reset fields through
+    /// [`AppState::config_repaired`][crate::AppState::config_repaired].
+    fn validate(&self) -> Vec<InvalidField> {
+        Vec::new()
+    }
+
     /// Returns the path to the application's config directory.
-    fn config_dir() -> PathBuf {
-        let project_dirs = ProjectDirs::from("", "", Self::app_name())
-            .expect("cannot determine the user's home directory");
-        project_dirs.config_dir().into()
+    ///
+    /// The directory is determined in the following order:
+    ///
+    /// 1. the `MAU_CONFIG_DIR` environment variable, if it's set,
+    /// 2. the `config` directory next to the executable, if there's a file named `portable` next
+    ///    to the executable (portable mode),
+    /// 3. the platform's config directory, eg. `~/.config/<app_name>` on Linux.
+    fn config_dir() -> Result<PathBuf, ConfigError> {
+        if let Some(dir) = crate::dirs::from_env("MAU_CONFIG_DIR") {
+            return Ok(dir);
+        }
+        if let Some(root) = crate::dirs::portable_root() {
+            return Ok(root.join("config"));
+        }
+        let project_dirs =
+            ProjectDirs::from("", "", Self::app_name()).ok_or(ConfigError::NoConfigDir)?;
+        Ok(project_dirs.config_dir().into())
+    }
+
+    /// Returns the name of config files, with the extension of the config's [format][Self::FORMAT].
+    fn config_file_name() -> String {
+        format!("config.{}", Self::FORMAT.extension())
     }
 
-    /// Returns the path to the `config.toml` file located in the application's config directory.
-    fn config_path() -> PathBuf {
-        Self::config_dir().join("config.toml")
+    /// Returns the path to the config file (`config.toml` by default) located in the
+    /// application's config directory.
+    fn config_path() -> Result<PathBuf, ConfigError> {
+        Ok(Self::config_dir()?.join(Self::config_file_name()))
     }
 
-    /// Loads the `config.toml` file, or creates a fresh one if it doesn't already exist.
+    /// Returns the path to the file of the profile with the given name, or the default profile's
+    /// file (ie. [`config_path`][Self::config_path]) if the name is `None`.
+    ///
+    /// Named profiles are stored in the `profiles` subdirectory of the config directory.
+    fn profile_path(name: Option<&str>) -> Result<PathBuf, ConfigError> {
+        match name {
+            Some(name) => {
+                profiles::validate_name(name)?;
+                Ok(Self::config_dir()?
+                    .join(profiles::PROFILES_DIR)
+                    .join(format!("{name}.{}", Self::FORMAT.extension())))
+            }
+            None => Self::config_path(),
+        }
+    }
+
+    /// Returns the names of all named profiles, in alphabetical order.
+    fn profiles() -> Result<Vec<String>, ConfigError> {
+        let dir = Self::config_dir()?.join(profiles::PROFILES_DIR);
+        let mut names = Vec::new();
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(names),
+            Err(error) => return Err(error.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_file()
+                && path.extension().and_then(|e| e.to_str()) == Some(Self::FORMAT.extension())
+            {
+                if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Returns the name of the selected profile, or `None` if the default profile is selected.
+    fn active_profile() -> Option<String> {
+        profiles::active(std::any::type_name::<Self>())
+    }
+
+    /// Selects the profile to load, or the default profile if the name is `None`.
+    ///
+    /// This only has an effect on the next load, and is meant to be called before the config is
+    /// loaded for the first time. Use [`switch_profile`][Self::switch_profile] to change profiles
+    /// while the app is running. [`App::config_profile`][crate::App::config_profile] calls this
+    /// automatically.
+    fn select_profile(name: Option<&str>) -> Result<(), ConfigError> {
+        profiles::select(std::any::type_name::<Self>(), name).map(|_| ())
+    }
+
+    /// Saves the current profile, then switches to another profile and reloads the config from
+    /// it. If the profile doesn't exist yet, it's created, inheriting from the default profile.
+    ///
+    /// Returns the fields that had to be reset, like [`load_and_repair`][Self::load_and_repair].
+    /// If loading the profile fails, the previous profile stays selected.
+    fn switch_profile(&mut self, name: Option<&str>) -> Result<Vec<InvalidField>, ConfigError> {
+        self.flush()?;
+        let key = std::any::type_name::<Self>();
+        let overrides = LAYERS
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|layers| Overrides::from_layer(&layers.overrides))
+            .unwrap_or_default();
+        let previous = profiles::select(key, name)?;
+        match Self::load_and_repair(&overrides) {
+            Ok((config, invalid_fields)) => {
+                *self = config;
+                Ok(invalid_fields)
+            }
+            Err(error) => {
+                profiles::select(key, previous.as_deref())?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Creates a new, empty profile, which inherits all values from the given parent profile, or
+    /// the default profile if `inherits` is `None`.
+    fn create_profile(name: &str, inherits: Option<&str>) -> Result<(), ConfigError> {
+        let path = Self::profile_path(Some(name))?;
+        if path.exists() {
+            return Err(ConfigError::ProfileExists(name.to_owned()));
+        }
+        if let Some(parent) = inherits {
+            if !Self::profile_path(Some(parent))?.is_file() {
+                return Err(ConfigError::UnknownProfile(parent.to_owned()));
+            }
+        }
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        Self::write_file(&path, layers::empty(), inherits)
+    }
+
+    /// Deletes a named profile. The selected profile cannot be deleted.
+    fn delete_profile(name: &str) -> Result<(), ConfigError> {
+        if profiles::active(std::any::type_name::<Self>()).as_deref() == Some(name) {
+            return Err(ConfigError::ProfileInUse(name.to_owned()));
+        }
+        match std::fs::remove_file(Self::profile_path(Some(name))?) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Err(ConfigError::UnknownProfile(name.to_owned()))
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Returns the path to the system-wide config file, which provides defaults for all users.
+    ///
+    /// By default this is `/etc/<app_name>/config.toml` on Linux and other Unix-likes,
+    /// `/Library/Application Support/<app_name>/config.toml` on macOS, and
+    /// `%ProgramData%\<app_name>\config.toml` on Windows. The extension depends on the
+    /// config's [format][Self::FORMAT].
+    fn system_config_path() -> Option<PathBuf> {
+        let app_name = Self::app_name();
+        let file_name = Self::config_file_name();
+        if cfg!(windows) {
+            std::env::var_os("ProgramData")
+                .map(|dir| PathBuf::from(dir).join(app_name).join(file_name))
+        } else if cfg!(target_os = "macos") {
+            Some(
+                Path::new("/Library/Application Support")
+                    .join(app_name)
+                    .join(file_name),
+            )
+        } else {
+            Some(Path::new("/etc").join(app_name).join(file_name))
+        }
+    }
+
+    /// Loads the config file, or creates a fresh one if it doesn't already exist.
+    ///
+    /// This is the same as [`load_with_overrides`][Self::load_with_overrides], but without any
+    /// explicit overrides.
     fn load_or_create() -> Result<Self, ConfigError> {
-        let config_dir = Self::config_dir();
-        let config_file = Self::config_path();
-        log::info!("loading config from {:?}", config_file);
-        std::fs::create_dir_all(config_dir)?;
-        if !config_file.is_file() {
-            let config = Self::default();
-            config.save()?;
-            Ok(config)
+        Self::load_with_overrides(&Overrides::new())
+    }
+
+    /// Loads the config from all [layers][Layer], creating a fresh config file if it doesn't
+    /// already exist.
+    ///
+    /// This is the same as [`load_and_repair`][Self::load_and_repair], but the list of fields that
+    /// were reset is only logged.
+    fn load_with_overrides(overrides: &Overrides) -> Result<Self, ConfigError> {
+        Self::load_and_repair(overrides).map(|(config, _)| config)
+    }
+
+    /// Loads the config from all [layers][Layer], creating a fresh config file if it doesn't
+    /// already exist.
+    ///
+    /// If the config file cannot be deserialized, it is moved aside (see
+    /// [`ConfigError::Deserialize`]) so that it doesn't get overwritten by the next save.
+    /// A broken system-wide config is ignored.
+    ///
+    /// If a named profile is [selected][Self::select_profile], it is loaded on top of the profiles
+    /// it inherits from, and created if it doesn't exist yet.
+    ///
+    /// Fields that don't pass [validation][Self::validate] are reset to their default values, and
+    /// returned alongside the config.
+    fn load_and_repair(overrides: &Overrides) -> Result<(Self, Vec<InvalidField>), ConfigError> {
+        // Checked up front, so that a wrong migration list is caught even on a fresh install.
+        let migrations = Self::migrations().len();
+        if migrations != Self::VERSION as usize {
+            return Err(ConfigError::MigrationCount {
+                migrations: migrations as u32,
+                version: Self::VERSION,
+            });
+        }
+
+        let config_dir = Self::config_dir()?;
+        let profile = profiles::active(std::any::type_name::<Self>());
+        let config_file = Self::profile_path(profile.as_deref())?;
+        std::fs::create_dir_all(config_file.parent().unwrap_or(&config_dir))?;
+
+        let system = Self::system_config_path()
+            .filter(|path| path.is_file())
+            .and_then(|path| {
+                log::info!("loading system-wide config from {:?}", path);
+                match Self::read_layer(&path) {
+                    Ok(layer) => Some(layer),
+                    Err(error) => {
+                        log::error!("ignoring system-wide config: {}", error);
+                        None
+                    }
+                }
+            });
+
+        // If there's no config file in the current format, look for one in a different format
+        // that can be converted. Named profiles are only ever stored in the current format.
+        let source_file = if config_file.is_file() {
+            Some((config_file.clone(), Self::FORMAT))
+        } else if profile.is_some() {
+            None
         } else {
-            let file = std::fs::read_to_string(&config_file)?;
-            let config: Self = match toml::from_str(&file) {
-                Ok(config) => config,
-                Err(error) => {
-                    log::error!("error while deserializing config file: {}", error);
-                    log::error!("falling back to default config");
-                    return Ok(Self::default());
+            Format::all().into_iter().find_map(|format| {
+                let path = config_dir.join(format!("config.{}", format.extension()));
+                path.is_file().then_some((path, format))
+            })
+        };
+        let mut user = if let Some((source_file, format)) = &source_file {
+            log::info!("loading config from {:?}", source_file);
+            let source = std::fs::read_to_string(source_file)?;
+            let mut user = format
+                .parse(&source)
+                .map_err(|error| broken_config(source_file, error))?;
+            Self::migrate(&mut user)?;
+            user
+        } else {
+            layers::empty()
+        };
+
+        let (parent_profile, inherited) = match &profile {
+            Some(name) => {
+                let parent = profiles::take_inherits(&mut user)?;
+                for key in Self::SHARED_KEYS {
+                    layers::remove(&mut user, key);
+                }
+                let inherited = Self::inherited_layer(name, parent.clone())?;
+                (parent, Some(inherited))
+            }
+            None => (None, None),
+        };
+
+        let mut layers = Layers {
+            default: toml::Value::try_from(Self::default())?,
+            system,
+            inherited,
+            parent_profile,
+            user,
+            environment: layers::environment_layer(Self::app_name()),
+            overrides: overrides.to_layer(),
+        };
+
+        // Layers are merged one by one, so that errors can be attributed to the right layer.
+        let mut effective = layers.base();
+        if let Err(error) = effective.clone().try_into::<Self>() {
+            log::error!("ignoring system-wide config: {}", error);
+            layers.system = None;
+            effective = layers.base();
+            if layers.inherited.is_some() {
+                if let Err(error) = effective.clone().try_into::<Self>() {
+                    return Err(ConfigError::InvalidLayer {
+                        layer: Layer::Inherited,
+                        error,
+                    });
                 }
+            }
+        }
+        layers::merge(&mut effective, &layers.user);
+        if let Err(error) = effective.clone().try_into::<Self>() {
+            let (source_file, _) = source_file.as_ref().unwrap();
+            return Err(broken_config(source_file, error));
+        }
+        layers::merge(&mut effective, &layers.environment);
+        if let Err(error) = effective.clone().try_into::<Self>() {
+            return Err(ConfigError::InvalidLayer {
+                layer: Layer::Environment,
+                error,
+            });
+        }
+        layers::merge(&mut effective, &layers.overrides);
+        let mut config: Self = effective
+            .try_into()
+            .map_err(|error| ConfigError::InvalidLayer {
+                layer: Layer::Override,
+                error,
+            })?;
+
+        let invalid_fields = config.validate();
+        if !invalid_fields.is_empty() {
+            let mut repaired = toml::Value::try_from(&config)?;
+            for field in &invalid_fields {
+                log::warn!(
+                    "config field {} is invalid, resetting it to default",
+                    field.path
+                );
+                match layers::get(&layers.default, &field.path) {
+                    Some(default) => layers::set(&mut repaired, &field.path, default.clone()),
+                    None => layers::remove(&mut repaired, &field.path),
+                }
+            }
+            config = repaired.try_into()?;
+        }
+
+        // `save` works from the new layers, but if it fails, the previous ones must stay in place,
+        // so that eg. `switch_profile` going back to the previous profile doesn't save it with
+        // this profile's layers.
+        let key = std::any::type_name::<Self>();
+        let previous = LAYERS.lock().unwrap().insert(key, layers);
+        // Preemptively save the config to the disk if any new keys have been added.
+        // I'm not sure if errors should be treated as fatal or not in this case.
+        if let Err(error) = config.save() {
+            let mut layers = LAYERS.lock().unwrap();
+            match previous {
+                Some(previous) => layers.insert(key, previous),
+                None => layers.remove(key),
             };
-            // Preemptively save the config to the disk if any new keys have been added.
-            // I'm not sure if errors should be treated as fatal or not in this case.
-            config.save()?;
-            Ok(config)
+            return Err(error);
+        }
+        if let Some((source_file, format)) = source_file {
+            if format != Self::FORMAT {
+                log::info!(
+                    "config was converted from {:?} to {:?}",
+                    format,
+                    Self::FORMAT
+                );
+                crate::fs::move_aside(&source_file, "converted")?;
+            }
+        }
+        Ok((config, invalid_fields))
+    }
+
+    /// Reads the profiles the named profile inherits from, starting at its parent, and merges
+    /// them on top of the default profile.
+    ///
+    /// Shared keys are only taken from the default profile.
+    fn inherited_layer(name: &str, parent: Option<String>) -> Result<toml::Value, ConfigError> {
+        let mut visited = vec![name.to_owned()];
+        let mut chain = Vec::new();
+        let mut parent = parent;
+        while let Some(name) = parent {
+            if visited.contains(&name) {
+                return Err(ConfigError::ProfileInheritanceCycle(name));
+            }
+            let path = Self::profile_path(Some(&name))?;
+            if !path.is_file() {
+                return Err(ConfigError::UnknownProfile(name));
+            }
+            let mut layer = Self::read_layer(&path)?;
+            parent = profiles::take_inherits(&mut layer)?;
+            for key in Self::SHARED_KEYS {
+                layers::remove(&mut layer, key);
+            }
+            chain.push(layer);
+            visited.push(name);
+        }
+
+        let default_file = Self::config_path()?;
+        let mut inherited = if default_file.is_file() {
+            Self::read_layer(&default_file)?
+        } else {
+            layers::empty()
+        };
+        for layer in chain.iter().rev() {
+            layers::merge(&mut inherited, layer);
         }
+        Ok(inherited)
+    }
+
+    /// Reads a single config file and upgrades it to the current schema version.
+    fn read_layer(path: &Path) -> Result<toml::Value, ConfigError> {
+        let file = std::fs::read_to_string(path)?;
+        let mut value = Self::FORMAT.parse(&file)?;
+        Self::migrate(&mut value)?;
+        Ok(value)
+    }
+
+    /// Returns which layer the effective value under the given dot-separated key (eg.
+    /// `window.maximized`) comes from.
+    ///
+    /// Returns `None` if there's no such key, or if the config hasn't been loaded yet.
+    fn value_source(key: &str) -> Option<Layer> {
+        LAYERS
+            .lock()
+            .unwrap()
+            .get(std::any::type_name::<Self>())
+            .and_then(|layers| layers.source(key))
+    }
+
+    /// Upgrades a raw config to the current schema version, by running all migrations from the
+    /// version the config was saved with.
+    ///
+    /// The version key is removed from the config in the process.
+    fn migrate(value: &mut toml::Value) -> Result<(), ConfigError> {
+        let migrations = Self::migrations();
+        let version = match value.as_table_mut().and_then(|t| t.remove(VERSION_KEY)) {
+            None => 0,
+            Some(toml::Value::Integer(version)) => {
+                u32::try_from(version).map_err(|_| ConfigError::InvalidVersion)?
+            }
+            Some(_) => return Err(ConfigError::InvalidVersion),
+        };
+        if version > Self::VERSION {
+            return Err(ConfigError::UnsupportedVersion {
+                found: version,
+                supported: Self::VERSION,
+            });
+        }
+
+        for (from, migration) in migrations.iter().enumerate().skip(version as usize) {
+            log::info!("migrating config from version {} to {}", from, from + 1);
+            migration(value).map_err(|error| ConfigError::Migration {
+                from: from as u32,
+                error,
+            })?;
+        }
+        Ok(())
     }
 
     /// Saves the config file to the application's config directory.
+    ///
+    /// Only the user [layer][Layer] is saved; values that come from the system-wide config,
+    /// environment variables, or overrides are not written to the user's config file.
+    ///
+    /// While a named profile is selected, the config is saved to the profile's file, and only
+    /// values that differ from the profiles it inherits from are written. [Shared
+    /// keys][Self::SHARED_KEYS] are written to the default profile instead.
     fn save(&self) -> Result<(), ConfigError> {
         // Assumes that `config_dir` was already created in `load_or_create`.
-        let config_file = Self::config_path();
-        std::fs::write(&config_file, toml::to_string(self)?)?;
+        let profile = profiles::active(std::any::type_name::<Self>());
+        let config_file = Self::profile_path(profile.as_deref())?;
+        let effective = toml::Value::try_from(self)?;
+        let mut guard = LAYERS.lock().unwrap();
+        let (mut user, parent_profile, pinned) = match guard.get_mut(std::any::type_name::<Self>())
+        {
+            Some(layers) => (
+                layers.update_user(&effective).clone(),
+                layers.parent_profile.clone(),
+                layers.pinned(),
+            ),
+            None => (effective.clone(), None, layers::empty()),
+        };
+
+        if profile.is_some() && !Self::SHARED_KEYS.is_empty() {
+            let default_file = Self::config_path()?;
+            let mut default_profile = if default_file.is_file() {
+                Self::read_layer(&default_file)?
+            } else {
+                layers::empty()
+            };
+            let mut changed = false;
+            for key in Self::SHARED_KEYS {
+                layers::remove(&mut user, key);
+                let value = layers::get(&effective, key);
+                let pinned = layers::get(&pinned, key).is_some();
+                if let Some(value) = value.filter(|_| !pinned) {
+                    if layers::get(&default_profile, key) != Some(value) {
+                        layers::set(&mut default_profile, key, value.clone());
+                        changed = true;
+                    }
+                }
+            }
+            if changed {
+                Self::write_file(&default_file, default_profile, None)?;
+            }
+        }
+
+        Self::write_file(&config_file, user, parent_profile.as_deref())
+    }
+
+    /// Writes a single config file, stamped with the current schema version and the name of the
+    /// profile it inherits from, if any.
+    fn write_file(
+        path: &Path,
+        layer: toml::Value,
+        inherits: Option<&str>,
+    ) -> Result<(), ConfigError> {
+        // The version is stamped at the very top of the file, so that it's easy to spot.
+        let mut table = toml::value::Table::new();
+        table.insert(
+            VERSION_KEY.to_owned(),
+            toml::Value::Integer(Self::VERSION.into()),
+        );
+        if let Some(inherits) = inherits {
+            table.insert(
+                profiles::INHERITS_KEY.to_owned(),
+                toml::Value::String(inherits.to_owned()),
+            );
+        }
+        if let toml::Value::Table(fields) = layer {
+            table.extend(fields);
+        }
+        let contents = Self::FORMAT.serialize(&toml::Value::Table(table))?;
+        crate::fs::write_atomically(path, contents.as_bytes(), true)?;
         Ok(())
     }
 
-    /// Writes values to the config and then saves it. This is recommended over using `save()`
-    /// manually when you want to modify the config.
+    /// Writes values to the config and schedules it to be saved. This is recommended over using
+    /// `save()` manually when you want to modify the config.
     ///
-    /// Note that this function, unlike `save()` is infallible. Instead it simply logs the error
-    /// on failure.
+    /// The config is not saved immediately, but rather after no writes have been made for
+    /// [`SAVE_DELAY`][Self::SAVE_DELAY], once [`save_if_idle`][Self::save_if_idle] is called.
+    /// The app's event loop takes care of that, as well as saving any pending writes when the app
+    /// exits.
     fn write(&mut self, f: impl FnOnce(&mut Self)) {
         f(self);
-        if let Err(error) = self.save() {
-            // TODO: Global error bus
-            log::error!("error while saving config: {error}");
+        saving::mark_dirty(std::any::type_name::<Self>());
+    }
+
+    /// Saves the config if there were any writes to it, and no writes have been made for
+    /// [`SAVE_DELAY`][Self::SAVE_DELAY].
+    ///
+    /// Note that this function, unlike `save()` is infallible. Instead it simply logs the error
+    /// on failure, and tries saving again after another [`SAVE_DELAY`][Self::SAVE_DELAY].
+    fn save_if_idle(&self) {
+        let key = std::any::type_name::<Self>();
+        if let Some(write) = saving::idle_write(key, Self::SAVE_DELAY) {
+            match self.save() {
+                Ok(()) => saving::mark_saved(key, write),
+                Err(error) => {
+                    // TODO: Global error bus
+                    log::error!("error while saving config: {error}");
+                    saving::mark_dirty(key);
+                }
+            }
+        }
+    }
+
+    /// Immediately saves the config if there are any pending writes to it.
+    ///
+    /// If saving fails, the writes stay pending.
+    fn flush(&self) -> Result<(), ConfigError> {
+        let key = std::any::type_name::<Self>();
+        if let Some(write) = saving::pending_write(key) {
+            self.save()?;
+            saving::mark_saved(key, write);
         }
+        Ok(())
     }
 }
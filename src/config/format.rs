@@ -0,0 +1,68 @@
+//! Config file formats.
+//!
+//! TOML is always available. Other formats can be enabled using cargo features:
+//!
+//! - `config-json` enables JSON,
+//! - `config-ron` enables [RON](https://github.com/ron-rs/ron).
+//!
+//! Regardless of the format, configs are converted to TOML values internally, so that layers and
+//! migrations work the same way for all formats. Because of that, `null`s are not supported in
+//! formats other than TOML; optional values should be omitted instead.
+
+use crate::error::ConfigError;
+
+/// A config file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Toml,
+    #[cfg(feature = "config-json")]
+    Json,
+    #[cfg(feature = "config-ron")]
+    Ron,
+}
+
+impl Format {
+    /// Returns all formats enabled through cargo features.
+    pub fn all() -> Vec<Format> {
+        vec![
+            Self::Toml,
+            #[cfg(feature = "config-json")]
+            Self::Json,
+            #[cfg(feature = "config-ron")]
+            Self::Ron,
+        ]
+    }
+
+    /// Returns the file extension used by the format, without the leading dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            #[cfg(feature = "config-json")]
+            Self::Json => "json",
+            #[cfg(feature = "config-ron")]
+            Self::Ron => "ron",
+        }
+    }
+
+    /// Parses a config file.
+    pub(crate) fn parse(self, source: &str) -> Result<toml::Value, ConfigError> {
+        Ok(match self {
+            Self::Toml => toml::from_str(source)?,
+            #[cfg(feature = "config-json")]
+            Self::Json => serde_json::from_str(source)?,
+            #[cfg(feature = "config-ron")]
+            Self::Ron => ron::from_str(source)?,
+        })
+    }
+
+    /// Serializes a config file.
+    pub(crate) fn serialize(self, value: &toml::Value) -> Result<String, ConfigError> {
+        Ok(match self {
+            Self::Toml => toml::to_string(value)?,
+            #[cfg(feature = "config-json")]
+            Self::Json => serde_json::to_string_pretty(value)?,
+            #[cfg(feature = "config-ron")]
+            Self::Ron => ron::ser::to_string_pretty(value, Default::default())?,
+        })
+    }
+}
@@ -0,0 +1,370 @@
+//! Layered configuration.
+//!
+//! The effective config is built by merging several layers on top of each other, from lowest to
+//! highest precedence:
+//!
+//! 1. the config's [`Default`] implementation,
+//! 2. the optional system-wide config file, which can be used for shipping defaults to a whole
+//!    organization,
+//! 3. the profiles the selected profile inherits from, if a named profile is selected,
+//! 4. the user's config file, or the selected profile's file,
+//! 5. `APPNAME_*` environment variables,
+//! 6. explicit overrides, eg. from the command line.
+//!
+//! Only the user layer is ever written back to the disk.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+/// A single configuration layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /// The config's `Default` implementation.
+    Default,
+    /// The system-wide config file.
+    System,
+    /// The default profile, or another profile the selected profile inherits from.
+    Inherited,
+    /// The user's config file.
+    User,
+    /// Environment variables.
+    Environment,
+    /// Explicit overrides passed in by the app.
+    Override,
+}
+
+/// Layers are passed to translations as their kebab-case names, so that they can be used as
+/// selectors.
+impl From<Layer> for mau_i18n::FormatArg<'_> {
+    fn from(layer: Layer) -> Self {
+        match layer {
+            Layer::Default => "default",
+            Layer::System => "system",
+            Layer::Inherited => "inherited",
+            Layer::User => "user",
+            Layer::Environment => "environment",
+            Layer::Override => "override",
+        }
+        .into()
+    }
+}
+
+/// Explicit overrides of config values, which take precedence over all other layers.
+///
+/// Keys are dot-separated paths into the config, eg. `window.maximized`.
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    values: Vec<(String, toml::Value)>,
+}
+
+impl Overrides {
+    /// Creates an empty set of overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the value under the given key.
+    pub fn set(&mut self, key: &str, value: impl Into<toml::Value>) {
+        self.values.push((key.to_owned(), value.into()));
+    }
+
+    /// Parses overrides from command line arguments of the form `--config key=value` or
+    /// `--config=key=value`. All other arguments are ignored.
+    ///
+    /// Values are parsed as TOML, and treated as plain strings if that fails, so both
+    /// `--config window.maximized=true` and `--config language=de-DE` work as expected.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut overrides = Self::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let pair = if arg == "--config" {
+                args.next()
+            } else {
+                arg.strip_prefix("--config=").map(|pair| pair.to_owned())
+            };
+            if let Some(pair) = pair {
+                match pair.split_once('=') {
+                    Some((key, value)) => overrides.set(key.trim(), parse_value(value.trim())),
+                    None => log::warn!("ignoring config override {:?} without a value", pair),
+                }
+            }
+        }
+        overrides
+    }
+
+    /// Converts a config layer back to overrides.
+    pub(crate) fn from_layer(layer: &toml::Value) -> Self {
+        fn flatten(value: &toml::Value, prefix: &str, overrides: &mut Overrides) {
+            match value {
+                toml::Value::Table(table) => {
+                    for (key, value) in table {
+                        let key = if prefix.is_empty() {
+                            key.clone()
+                        } else {
+                            format!("{prefix}.{key}")
+                        };
+                        flatten(value, &key, overrides);
+                    }
+                }
+                value => overrides.set(prefix, value.clone()),
+            }
+        }
+
+        let mut overrides = Self::new();
+        flatten(layer, "", &mut overrides);
+        overrides
+    }
+
+    /// Converts the overrides to a config layer.
+    pub(crate) fn to_layer(&self) -> toml::Value {
+        let mut layer = empty();
+        for (key, value) in &self.values {
+            set(&mut layer, key, value.clone());
+        }
+        layer
+    }
+}
+
+/// The layers the currently loaded config was merged from.
+pub(crate) struct Layers {
+    pub(crate) default: toml::Value,
+    pub(crate) system: Option<toml::Value>,
+    pub(crate) inherited: Option<toml::Value>,
+    /// The profile the selected profile inherits from, as stored in its file.
+    pub(crate) parent_profile: Option<String>,
+    pub(crate) user: toml::Value,
+    pub(crate) environment: toml::Value,
+    pub(crate) overrides: toml::Value,
+}
+
+impl Layers {
+    /// Returns the layer the value under the given key comes from.
+    pub(crate) fn source(&self, key: &str) -> Option<Layer> {
+        [
+            (Layer::Override, Some(&self.overrides)),
+            (Layer::Environment, Some(&self.environment)),
+            (Layer::User, Some(&self.user)),
+            (Layer::Inherited, self.inherited.as_ref()),
+            (Layer::System, self.system.as_ref()),
+            (Layer::Default, Some(&self.default)),
+        ]
+        .into_iter()
+        .find(|(_, value)| {
+            value
+                .map(|value| get(value, key).is_some())
+                .unwrap_or(false)
+        })
+        .map(|(layer, _)| layer)
+    }
+
+    /// Returns the merged layers below the user layer.
+    pub(crate) fn base(&self) -> toml::Value {
+        let mut base = self.default.clone();
+        if let Some(system) = &self.system {
+            merge(&mut base, system);
+        }
+        if let Some(inherited) = &self.inherited {
+            merge(&mut base, inherited);
+        }
+        base
+    }
+
+    /// Returns the layers whose values should only be repeated in the user layer if the user
+    /// changes them, or `None` if there are no such layers.
+    ///
+    /// While a named profile is selected, this is everything below the user layer, so that the
+    /// profile only stores its differences from the profiles it inherits from.
+    fn shared(&self) -> Option<toml::Value> {
+        if self.inherited.is_some() {
+            Some(self.base())
+        } else {
+            self.system.clone()
+        }
+    }
+
+    /// Returns the merged layers above the user layer.
+    pub(crate) fn pinned(&self) -> toml::Value {
+        let mut pinned = self.environment.clone();
+        merge(&mut pinned, &self.overrides);
+        pinned
+    }
+
+    /// Extracts the user layer out of the effective config, and stores it as the new user layer.
+    ///
+    /// A value ends up in the user layer if it was already there, or if it differs from what the
+    /// layers below specify. Values coming from layers above the user layer are never written,
+    /// and whatever the user had set previously is kept instead. When there's no system-wide
+    /// config and no inherited profile, all values are written, so that the user can see every
+    /// available option.
+    pub(crate) fn update_user(&mut self, effective: &toml::Value) -> &toml::Value {
+        let base = self.base();
+        let shared = self.shared();
+        let pinned = self.pinned();
+        if let toml::Value::Table(effective) = effective {
+            let user = user_table(
+                effective,
+                Some(&base),
+                shared.as_ref(),
+                Some(&self.user),
+                Some(&pinned),
+            );
+            self.user = toml::Value::Table(user);
+        }
+        &self.user
+    }
+}
+
+/// The layers of each loaded config, keyed by the config's type name like in
+/// [`saving`][super::saving].
+pub(crate) static LAYERS: Lazy<Mutex<HashMap<&'static str, Layers>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn user_table(
+    effective: &toml::value::Table,
+    base: Option<&toml::Value>,
+    shared: Option<&toml::Value>,
+    previous: Option<&toml::Value>,
+    pinned: Option<&toml::Value>,
+) -> toml::value::Table {
+    let mut user = toml::value::Table::new();
+    for (key, value) in effective {
+        let base = base.and_then(|v| v.get(key));
+        let shared = shared.and_then(|v| v.get(key));
+        let previous = previous.and_then(|v| v.get(key));
+        let pinned = pinned.and_then(|v| v.get(key));
+        match (value, pinned) {
+            (toml::Value::Table(table), None | Some(toml::Value::Table(_))) => {
+                let table = user_table(table, base, shared, previous, pinned);
+                if !table.is_empty() || previous.is_some() {
+                    user.insert(key.clone(), toml::Value::Table(table));
+                }
+            }
+            (_, Some(_)) => {
+                if let Some(previous) = previous {
+                    user.insert(key.clone(), previous.clone());
+                }
+            }
+            (value, None) => {
+                if shared.is_none() || base != Some(value) || previous.is_some() {
+                    user.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+    user
+}
+
+/// Returns an empty layer.
+pub(crate) fn empty() -> toml::Value {
+    toml::Value::Table(toml::value::Table::new())
+}
+
+/// Merges `layer` on top of `base`. Tables are merged recursively, and all other values are
+/// replaced.
+pub(crate) fn merge(base: &mut toml::Value, layer: &toml::Value) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer.clone(),
+    }
+}
+
+/// Returns the value under the given dot-separated key.
+pub(crate) fn get<'v>(value: &'v toml::Value, key: &str) -> Option<&'v toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, part| value.get(part))
+}
+
+/// Sets the value under the given dot-separated key, creating tables along the way if needed.
+pub(crate) fn set(value: &mut toml::Value, key: &str, new_value: toml::Value) {
+    let mut parts = key.split('.').peekable();
+    let mut value = value;
+    while let Some(part) = parts.next() {
+        if !value.is_table() {
+            *value = empty();
+        }
+        let table = value.as_table_mut().unwrap();
+        if parts.peek().is_none() {
+            table.insert(part.to_owned(), new_value);
+            return;
+        }
+        value = table.entry(part.to_owned()).or_insert_with(empty);
+    }
+}
+
+/// Removes the value under the given dot-separated key.
+pub(crate) fn remove(value: &mut toml::Value, key: &str) {
+    let (parent, name) = match key.rsplit_once('.') {
+        Some((parent, name)) => (get_mut(value, parent), name),
+        None => (Some(value), key),
+    };
+    if let Some(toml::Value::Table(table)) = parent {
+        table.remove(name);
+    }
+}
+
+fn get_mut<'v>(value: &'v mut toml::Value, key: &str) -> Option<&'v mut toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, part| value.get_mut(part))
+}
+
+/// Parses a value passed in from outside of a config file. Values that aren't valid TOML are
+/// treated as strings.
+///
+/// The value must be a single TOML value or inline table; anything that would define more keys
+/// after it, like `1\nother = 2`, is treated as a string too.
+pub(crate) fn parse_value(value: &str) -> toml::Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {value}"))
+        .ok()
+        .filter(|table| table.len() == 1)
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()))
+}
+
+/// Builds the environment layer from variables prefixed with `APPNAME_`.
+///
+/// The app name is converted to uppercase, with dashes replaced by underscores. The rest of the
+/// variable's name is converted to lowercase, and double underscores separate nested keys, so
+/// eg. `MY_APP_WINDOW__MAXIMIZED=true` sets `window.maximized` for the app `my-app`.
+///
+/// Variables whose names or values aren't valid Unicode are skipped.
+pub(crate) fn environment_layer(app_name: &str) -> toml::Value {
+    let prefix = format!("{}_", app_name.to_uppercase().replace('-', "_"));
+    let mut layer = empty();
+    for (name, value) in std::env::vars_os() {
+        let name = match name.into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+        if let Some(key) = name.strip_prefix(&prefix) {
+            let value = match value.into_string() {
+                Ok(value) => value,
+                Err(_) => {
+                    log::warn!(
+                        "ignoring environment variable {} with non-Unicode value",
+                        name
+                    );
+                    continue;
+                }
+            };
+            let key = key.to_lowercase().replace("__", ".");
+            log::debug!(
+                "config key {} overridden by environment variable {}",
+                key,
+                name
+            );
+            set(&mut layer, &key, parse_value(&value));
+        }
+    }
+    layer
+}
@@ -0,0 +1,84 @@
+//! Named config profiles.
+//!
+//! Besides the default profile stored in the main config file, users can keep any number of named
+//! profiles in the `profiles` subdirectory of the config directory, eg. `profiles/tablet.toml`.
+//! A named profile inherits all values it doesn't set from the default profile, or from another
+//! named profile specified under the `inherits` key.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::error::ConfigError;
+
+/// The key under which the parent profile's name is stored in profile files.
+pub(crate) const INHERITS_KEY: &str = "inherits";
+
+/// The name of the directory profiles are stored in, relative to the config directory.
+pub(crate) const PROFILES_DIR: &str = "profiles";
+
+/// The currently selected profile of each config type, keyed by its type name. Config types
+/// without an entry use the default profile.
+static ACTIVE_PROFILES: Lazy<Mutex<HashMap<&'static str, String>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the currently selected profile of the config.
+pub(crate) fn active(config: &'static str) -> Option<String> {
+    ACTIVE_PROFILES.lock().unwrap().get(config).cloned()
+}
+
+/// Selects a profile of the config, and returns the previously selected one.
+pub(crate) fn select(
+    config: &'static str,
+    name: Option<&str>,
+) -> Result<Option<String>, ConfigError> {
+    if let Some(name) = name {
+        validate_name(name)?;
+    }
+    let mut active = ACTIVE_PROFILES.lock().unwrap();
+    Ok(match name {
+        Some(name) => active.insert(config, name.to_owned()),
+        None => active.remove(config),
+    })
+}
+
+/// Checks whether the profile name can be safely used as a file name.
+pub(crate) fn validate_name(name: &str) -> Result<(), ConfigError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ');
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidProfileName(name.to_owned()))
+    }
+}
+
+/// Removes the parent profile's name from a profile layer, and returns it.
+pub(crate) fn take_inherits(layer: &mut toml::Value) -> Result<Option<String>, ConfigError> {
+    match layer.as_table_mut().and_then(|t| t.remove(INHERITS_KEY)) {
+        None => Ok(None),
+        Some(toml::Value::String(name)) => {
+            validate_name(&name)?;
+            Ok(Some(name))
+        }
+        Some(other) => Err(ConfigError::InvalidProfileName(other.to_string())),
+    }
+}
+
+/// Parses the profile to use from command line arguments of the form `--profile name` or
+/// `--profile=name`. All other arguments are ignored.
+pub fn profile_from_args(args: impl IntoIterator<Item = String>) -> Option<String> {
+    let mut args = args.into_iter();
+    let mut profile = None;
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            profile = args.next();
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_owned());
+        }
+    }
+    profile
+}
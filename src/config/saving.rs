@@ -0,0 +1,49 @@
+//! Debounced saving.
+//!
+//! Writing to the config only marks it as dirty; it's saved once no writes have been made for a
+//! while. This avoids doing disk I/O every frame when eg. a slider bound to a config value is
+//! being dragged.
+//!
+//! Each config type is tracked separately, keyed by its type name.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+
+/// The time of the last write to each config that hasn't been saved yet.
+static LAST_UNSAVED_WRITE: Lazy<Mutex<HashMap<&'static str, Instant>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Marks the config as dirty.
+pub(crate) fn mark_dirty(config: &'static str) {
+    LAST_UNSAVED_WRITE
+        .lock()
+        .unwrap()
+        .insert(config, Instant::now());
+}
+
+/// Returns the time of the last unsaved write, if the config is dirty and no writes have been
+/// made for at least `quiet_period`.
+pub(crate) fn idle_write(config: &'static str, quiet_period: Duration) -> Option<Instant> {
+    LAST_UNSAVED_WRITE
+        .lock()
+        .unwrap()
+        .get(config)
+        .copied()
+        .filter(|time| time.elapsed() >= quiet_period)
+}
+
+/// Returns the time of the last unsaved write, if the config is dirty.
+pub(crate) fn pending_write(config: &'static str) -> Option<Instant> {
+    LAST_UNSAVED_WRITE.lock().unwrap().get(config).copied()
+}
+
+/// Marks the config as clean after it was saved, unless it was written to again since `write`.
+pub(crate) fn mark_saved(config: &'static str, write: Instant) {
+    let mut last_writes = LAST_UNSAVED_WRITE.lock().unwrap();
+    if last_writes.get(config) == Some(&write) {
+        last_writes.remove(config);
+    }
+}
@@ -0,0 +1,42 @@
+//! Config validation.
+
+use mau_i18n::translate_enum::TranslateEnum;
+
+/// A config field that failed validation.
+pub struct InvalidField {
+    /// The dot-separated path to the field, eg. `window.width`.
+    pub path: String,
+    /// The reason why the field is invalid, which can be displayed to the user.
+    pub reason: Box<dyn TranslateEnum>,
+}
+
+impl InvalidField {
+    /// Creates a new invalid field error.
+    pub fn new(path: impl Into<String>, reason: impl TranslateEnum + 'static) -> Self {
+        Self {
+            path: path.into(),
+            reason: Box::new(reason),
+        }
+    }
+}
+
+impl std::fmt::Debug for InvalidField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InvalidField({})", self.path)
+    }
+}
+
+/// Common reasons for config values being invalid.
+#[derive(Debug, Clone, mau_i18n::TranslateEnum)]
+#[prefix = "config-invalid"]
+#[ftl("i18n/en-US.ftl")]
+pub enum InvalidValue {
+    /// The value is outside of the allowed range.
+    OutOfRange { min: f64, max: f64 },
+    /// The value must be greater than zero.
+    NotPositive,
+    /// The value is not one of the allowed choices.
+    UnknownChoice { value: String },
+    /// The language code is invalid, or there are no translations for it.
+    UnknownLanguage { code: String },
+}
@@ -0,0 +1,26 @@
+//! Resolution of the directories app files are stored in.
+
+use std::path::PathBuf;
+
+/// The name of the file which enables portable mode when placed next to the executable.
+const PORTABLE_MARKER: &str = "portable";
+
+/// Returns the directory containing the executable if portable mode is enabled, which is done by
+/// placing a file named `portable` next to the executable.
+///
+/// In portable mode, all app files are kept beside the executable instead of the user's home
+/// directory, so that the app can be carried around on a USB stick.
+pub(crate) fn portable_root() -> Option<PathBuf> {
+    let executable = std::env::current_exe().ok()?;
+    let root = executable.parent()?;
+    root.join(PORTABLE_MARKER)
+        .is_file()
+        .then(|| root.to_owned())
+}
+
+/// Returns the directory set by the given environment variable, if it's set and not empty.
+pub(crate) fn from_env(variable: &str) -> Option<PathBuf> {
+    std::env::var_os(variable)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
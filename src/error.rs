@@ -1,51 +1,123 @@
 //! Error enums.
 
+use std::path::PathBuf;
+
+use mau_i18n::TranslateEnum;
 use thiserror::Error;
 
-// TODO: i18n support for all of this
+use crate::config::Layer;
+use crate::image::ImageFormat;
 
 /// An error during the app's lifetime.
-#[derive(Debug, Error)]
+///
+/// All errors are translated using mau's built-in translations, which are merged into every
+/// language loaded by the app's [`LanguageMap`][crate::i18n::LanguageMap].
+#[derive(Debug, Error, TranslateEnum)]
 pub enum Error {
     #[error("Config error: {0}")]
+    #[transparent]
     Config(#[from] ConfigError),
     #[error("Backend error: {0}")]
+    #[transparent]
     Backend(#[from] mau_ui::backend::Error),
     #[error("Clipboard error: {0}")]
+    #[transparent]
     Clipboard(#[from] ClipboardError),
+    #[error("Storage error: {0}")]
+    #[transparent]
+    Storage(#[from] StorageError),
+    #[error("Image error: {0}")]
+    #[transparent]
+    Image(#[from] ImageError),
 }
 
 /// An error while loading or saving the app's config file.
-#[derive(Debug, Error)]
+///
+/// Source errors are kept as they are, so they're available through [`std::error::Error::source`],
+/// and are passed to translations as their messages.
+#[derive(Debug, Error, TranslateEnum)]
+#[prefix = "error-config"]
+#[ftl("i18n/en-US.ftl")]
+#[ftl_display]
 pub enum ConfigError {
-    #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
-    #[error("TOML serialization error: {0}")]
+    NoConfigDir,
     TomlSerialize(#[from] toml::ser::Error),
+    TomlDeserialize(#[from] toml::de::Error),
+    Deserialize {
+        error: String,
+        moved_to: PathBuf,
+    },
+    #[cfg(feature = "config-json")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "config-ron")]
+    RonDeserialize(#[from] ron::error::SpannedError),
+    #[cfg(feature = "config-ron")]
+    RonSerialize(#[from] ron::Error),
+
+    InvalidLayer {
+        layer: Layer,
+        #[source]
+        error: toml::de::Error,
+    },
+
+    InvalidVersion,
+    UnsupportedVersion {
+        found: u32,
+        supported: u32,
+    },
+    Migration {
+        from: u32,
+        error: String,
+    },
+    MigrationCount {
+        migrations: u32,
+        version: u32,
+    },
+
+    InvalidProfileName(#[arg = "name"] String),
+    UnknownProfile(#[arg = "name"] String),
+    ProfileExists(#[arg = "name"] String),
+    ProfileInUse(#[arg = "name"] String),
+    ProfileInheritanceCycle(#[arg = "name"] String),
 
-    #[error("config was already loaded in a previous call to load_or_create()")]
     ConfigIsAlreadyLoaded,
 }
 
-#[derive(Debug, Error)]
+/// An error while reading or writing app storage.
+#[derive(Debug, Error, TranslateEnum)]
+#[prefix = "error-storage"]
+#[ftl("i18n/en-US.ftl")]
+#[ftl_display]
+pub enum StorageError {
+    Io(#[from] std::io::Error),
+    NoStorageDir,
+    TomlSerialize(#[from] toml::ser::Error),
+    TomlDeserialize(#[from] toml::de::Error),
+    InvalidValue {
+        key: String,
+        #[source]
+        error: toml::de::Error,
+    },
+    InvalidName {
+        name: String,
+    },
+}
+
+#[derive(Debug, Error, TranslateEnum)]
+#[prefix = "error-clipboard"]
+#[ftl("i18n/en-US.ftl")]
+#[ftl_display]
 pub enum ClipboardError {
-    #[error("Clipboard content is uninitialized")]
     Uninitialized,
-    #[error("Saving to clipboard failed: {error}")]
     SaveFailed { error: String },
-    #[error("Clipboard does not contain text")]
     DoesNotContainText,
-    #[error("Clipboard does not contain an image")]
     DoesNotContainAnImage,
-    #[error("Clipboard content is unavailable")]
+    DoesNotContainFormat { format: String },
     ContentUnavailable,
-    #[error("Clipboard is not supported on your platform")]
     NotSupported,
-    #[error("Clipboard is occupied by another application. Try again")]
     Occupied,
-    #[error("Cannot convert data to/from a clipboard-specific format. Try again or report a bug")]
     ConversionFailed,
-    #[error("Unknown clipboard error: {error}")]
     Unknown { error: String },
 }
 
@@ -61,10 +133,25 @@ impl From<arboard::Error> for ClipboardError {
     }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Error, TranslateEnum)]
+#[prefix = "error-language"]
+#[ftl("i18n/en-US.ftl")]
+#[ftl_display]
 pub enum LanguageError {
-    #[error("No translations available for language {0}")]
-    NoTranslations(String),
-    #[error("Language {0} has an invalid FTL translation file")]
-    InvalidFTL(String),
+    NoTranslations(#[arg = "language"] String),
+    InvalidFTL(#[arg = "language"] String),
+}
+
+/// An error while loading or saving an image.
+#[derive(Debug, Error, TranslateEnum)]
+#[prefix = "error-image"]
+#[ftl("i18n/en-US.ftl")]
+#[ftl_display]
+pub enum ImageError {
+    Io(#[from] std::io::Error),
+    UnknownFormat,
+    FormatDisabled(#[arg = "format"] ImageFormat),
+    EncodingNotSupported(#[arg = "format"] ImageFormat),
+    Decode { error: String },
+    Encode { error: String },
 }
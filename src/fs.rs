@@ -0,0 +1,52 @@
+//! Filesystem utilities.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Appends a suffix to the path's file name, eg. `config.toml` with the suffix `.bak` becomes
+/// `config.toml.bak`.
+pub(crate) fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Writes to a file such that a crash in the middle of writing never leaves a half-written file
+/// behind.
+///
+/// The contents are first written to a temporary file, which is flushed to the disk and then
+/// renamed over the target file. If `backup` is true, the previous version of the file is kept
+/// next to it, with a `.bak` suffix.
+pub(crate) fn write_atomically(path: &Path, contents: &[u8], backup: bool) -> std::io::Result<()> {
+    let temp_path = with_suffix(path, ".tmp");
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    if backup && path.is_file() {
+        std::fs::copy(path, with_suffix(path, ".bak"))?;
+    }
+    std::fs::rename(&temp_path, path)?;
+    // On Unix, the rename itself is only durable once the directory is synced.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Moves a file out of the way by renaming it such that it has the current timestamp in its
+/// name. Returns the new path of the file.
+pub(crate) fn move_aside(path: &Path, reason: &str) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let new_path = with_suffix(path, &format!(".{reason}-{timestamp}"));
+    std::fs::rename(path, &new_path)?;
+    Ok(new_path)
+}
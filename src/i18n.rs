@@ -7,6 +7,13 @@ pub use mau_i18n::*;
 
 use crate::LanguageError;
 
+/// mau's built-in translations, for error messages and built-in screens.
+///
+/// Only English is shipped for now; apps can translate these messages by defining them in their
+/// own FTL files, which take precedence. The render backend's messages are shipped by the backend
+/// crate and loaded alongside these.
+const MAU_FTL: &str = include_str!("../i18n/en-US.ftl");
+
 /// Initialization function for language maps.
 ///
 /// This has to be a separate trait to make `LanguageMap` object-safe.
@@ -25,25 +32,79 @@ pub trait LanguageMap {
     /// Returns the FTL source code for the language with the given locale code.
     fn get(&self, code: &str) -> Option<&str>;
 
+    /// Returns the app's own functions, which are registered on every bundle this map loads, in
+    /// addition to the built-in `NUMBER` and `DATETIME`.
+    fn functions(&self) -> Functions {
+        Functions::new()
+    }
+
     /// Loads the language with the given locale code.
+    ///
+    /// The language falls back to its parent language and then to [`FALLBACK_LANGUAGE`] for
+    /// messages that aren't translated, so `de-AT` is looked up in `de-AT`, `de`, and `en-US`, in
+    /// that order. Links missing from the map are skipped, but either the language itself or its
+    /// parent must be available.
+    ///
+    /// mau's built-in translations are merged into the `en-US` link, so that framework errors can
+    /// be translated.
     fn load_language(&self, code: &str) -> Result<Language, LanguageError> {
-        if let Some(ftl_source) = self.get(code) {
-            let language = Language::load(code, ftl_source);
-            let language = match language {
-                Ok(language) => language,
-                Err(error) => {
-                    log::error!("error while loading language:");
-                    log::error!("{}", error);
-                    return Err(LanguageError::InvalidFTL(code.to_string()));
+        let no_translations = || LanguageError::NoTranslations(code.to_string());
+
+        let functions = self.functions();
+        let mut language: Option<Language> = None;
+        if code != FALLBACK_LANGUAGE {
+            let mut chain = vec![code];
+            // The primary language subtag always comes first in a locale code.
+            if let Some((parent, _)) = code.split_once(['-', '_']) {
+                chain.push(parent);
+            }
+            for link in chain {
+                if let Some(ftl_source) = self.get(link) {
+                    let link = load_link(link, &[ftl_source], &functions)?;
+                    language = Some(match language {
+                        Some(language) => language.with_fallback(link),
+                        None => link,
+                    });
                 }
-            };
-            Ok(language)
-        } else {
-            Err(LanguageError::NoTranslations(code.to_string()))
+            }
+            if language.is_none() {
+                return Err(no_translations());
+            }
         }
+
+        let fallback_source = match self.get(FALLBACK_LANGUAGE) {
+            Some(ftl_source) => ftl_source,
+            None if language.is_some() => "",
+            None => return Err(no_translations()),
+        };
+        let fallback = load_link(
+            FALLBACK_LANGUAGE,
+            &[MAU_FTL, mau_ui::backend::FTL, fallback_source],
+            &functions,
+        )?;
+        Ok(match language {
+            Some(language) => language.with_fallback(fallback),
+            None => fallback,
+        })
     }
 }
 
+/// The language every other language falls back to.
+pub const FALLBACK_LANGUAGE: &str = "en-US";
+
+/// Loads a single link of a language's fallback chain.
+fn load_link(
+    code: &str,
+    ftl_sources: &[&str],
+    functions: &Functions,
+) -> Result<Language, LanguageError> {
+    Language::load_with_functions(code, ftl_sources, functions).map_err(|error| {
+        log::error!("error while loading language:");
+        log::error!("{}", error);
+        LanguageError::InvalidFTL(code.to_string())
+    })
+}
+
 /// The empty tuple can be used as a language map for testing purposes.
 impl LanguageMapInit for () {
     fn new() -> Self {}
@@ -0,0 +1,427 @@
+//! Loading and saving image files.
+//!
+//! Support for each file format is enabled with a feature:
+//!
+//! - `image-png` for PNG,
+//! - `image-jpeg` for JPEG,
+//! - `image-webp` for WebP (decoding only),
+//! - `image-qoi` for QOI.
+//!
+//! Images are decoded into straight (non-premultiplied) RGBA, with their EXIF orientation already
+//! applied, and can be uploaded straight to the render backend as images or framebuffers.
+//!
+//! Note that framebuffers hold premultiplied colors, because drawing onto a transparent
+//! framebuffer with alpha blending multiplies the colors by their alpha. The framebuffer functions
+//! in this module convert between the two representations automatically.
+
+use std::fmt;
+use std::path::Path;
+
+use ::image::RgbaImage;
+use mau_renderer::{Framebuffer as _, RenderBackend};
+use mau_ui::{Backend, Framebuffer, Image};
+
+use crate::error::ImageError;
+
+/// An image file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    WebP,
+    Qoi,
+}
+
+impl ImageFormat {
+    /// Guesses the format from a file extension, ignoring case.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" | "jfif" => Some(Self::Jpeg),
+            "webp" => Some(Self::WebP),
+            "qoi" => Some(Self::Qoi),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from a path's extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// Detects the format from the magic number at the start of a file.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+            Some(Self::WebP)
+        } else if bytes.starts_with(b"qoif") {
+            Some(Self::Qoi)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether support for the format is enabled.
+    pub fn is_enabled(self) -> bool {
+        match self {
+            Self::Png => cfg!(feature = "image-png"),
+            Self::Jpeg => cfg!(feature = "image-jpeg"),
+            Self::WebP => cfg!(feature = "image-webp"),
+            Self::Qoi => cfg!(feature = "image-qoi"),
+        }
+    }
+
+    /// Returns whether images can be encoded in this format.
+    pub fn can_encode(self) -> bool {
+        self.is_enabled() && self != Self::WebP
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::WebP => "WebP",
+            Self::Qoi => "QOI",
+        })
+    }
+}
+
+impl From<ImageFormat> for mau_i18n::FormatArg<'_> {
+    fn from(format: ImageFormat) -> Self {
+        format.to_string().into()
+    }
+}
+
+/// Decodes an image file from memory, detecting its format.
+pub fn decode(bytes: &[u8]) -> Result<RgbaImage, ImageError> {
+    let format = ImageFormat::detect(bytes).ok_or(ImageError::UnknownFormat)?;
+    decode_with_format(bytes, format)
+}
+
+/// Decodes an image file in the given format from memory.
+pub fn decode_with_format(bytes: &[u8], format: ImageFormat) -> Result<RgbaImage, ImageError> {
+    let image = decode_pixels(bytes, format)?;
+    Ok(match exif_orientation(bytes, format) {
+        Some(orientation) => apply_orientation(image, orientation),
+        None => image,
+    })
+}
+
+/// Loads an image file, detecting its format.
+pub fn load(path: impl AsRef<Path>) -> Result<RgbaImage, ImageError> {
+    decode(&std::fs::read(path)?)
+}
+
+/// Encodes an image in the given format. JPEG images are encoded without the alpha channel.
+pub fn encode(image: &RgbaImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    if !format.is_enabled() {
+        return Err(ImageError::FormatDisabled(format));
+    }
+    if !format.can_encode() {
+        return Err(ImageError::EncodingNotSupported(format));
+    }
+    encode_pixels(image, format)
+}
+
+/// Saves an image to a file, in the format given by the path's extension.
+pub fn save(image: &RgbaImage, path: impl AsRef<Path>) -> Result<(), ImageError> {
+    let path = path.as_ref();
+    let format = ImageFormat::from_path(path).ok_or(ImageError::UnknownFormat)?;
+    let bytes = encode(image, format)?;
+    crate::fs::write_atomically(path, &bytes, false)?;
+    Ok(())
+}
+
+/// Uploads an image to the render backend.
+pub fn to_image(backend: &mut Backend, image: &RgbaImage) -> Image {
+    backend.create_image_from_rgba(image.width(), image.height(), image)
+}
+
+/// Uploads an image to a new framebuffer.
+pub fn to_framebuffer(backend: &mut Backend, image: &RgbaImage) -> Framebuffer {
+    let mut premultiplied = image.clone();
+    premultiply(&mut premultiplied);
+    let mut framebuffer = backend.create_framebuffer(image.width(), image.height());
+    framebuffer.upload_rgba((0, 0), image.dimensions(), &premultiplied);
+    framebuffer
+}
+
+/// Decodes an image file from memory straight into a render backend image.
+pub fn decode_image(backend: &mut Backend, bytes: &[u8]) -> Result<Image, ImageError> {
+    Ok(to_image(backend, &decode(bytes)?))
+}
+
+/// Loads an image file into a render backend image.
+pub fn load_image(backend: &mut Backend, path: impl AsRef<Path>) -> Result<Image, ImageError> {
+    Ok(to_image(backend, &load(path)?))
+}
+
+/// Decodes an image file from memory into a new framebuffer.
+pub fn decode_framebuffer(backend: &mut Backend, bytes: &[u8]) -> Result<Framebuffer, ImageError> {
+    Ok(to_framebuffer(backend, &decode(bytes)?))
+}
+
+/// Loads an image file into a new framebuffer.
+pub fn load_framebuffer(
+    backend: &mut Backend,
+    path: impl AsRef<Path>,
+) -> Result<Framebuffer, ImageError> {
+    Ok(to_framebuffer(backend, &load(path)?))
+}
+
+/// Downloads the contents of a framebuffer into an image with straight alpha.
+pub fn from_framebuffer(framebuffer: &Framebuffer) -> RgbaImage {
+    let (width, height) = framebuffer.size();
+    let mut image = RgbaImage::new(width, height);
+    framebuffer.download_rgba((0, 0), (width, height), &mut image);
+    unpremultiply(&mut image);
+    image
+}
+
+/// Saves the contents of a framebuffer to a file, in the format given by the path's extension.
+pub fn save_framebuffer(
+    framebuffer: &Framebuffer,
+    path: impl AsRef<Path>,
+) -> Result<(), ImageError> {
+    save(&from_framebuffer(framebuffer), path)
+}
+
+/// Multiplies the color channels of each pixel by its alpha.
+pub fn premultiply(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        for channel in &mut pixel.0[..3] {
+            *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+/// Divides the color channels of each pixel by its alpha, reversing [`premultiply`].
+pub fn unpremultiply(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        let alpha = pixel[3] as u32;
+        if alpha == 0 {
+            continue;
+        }
+        for channel in &mut pixel.0[..3] {
+            *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+        }
+    }
+}
+
+#[allow(unused)]
+fn decode_error(error: impl ToString) -> ImageError {
+    ImageError::Decode {
+        error: error.to_string(),
+    }
+}
+
+#[allow(unused)]
+fn encode_error(error: impl ToString) -> ImageError {
+    ImageError::Encode {
+        error: error.to_string(),
+    }
+}
+
+#[allow(unused)]
+fn decode_with_image_crate(
+    bytes: &[u8],
+    format: ::image::ImageFormat,
+) -> Result<RgbaImage, ImageError> {
+    ::image::load_from_memory_with_format(bytes, format)
+        .map(|image| image.into_rgba8())
+        .map_err(decode_error)
+}
+
+#[allow(unused)]
+fn encode_with_image_crate(
+    image: ::image::DynamicImage,
+    format: ::image::ImageOutputFormat,
+) -> Result<Vec<u8>, ImageError> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut bytes, format).map_err(encode_error)?;
+    Ok(bytes.into_inner())
+}
+
+/// Decodes the pixels of an image, without applying its orientation.
+#[allow(unused_variables)]
+fn decode_pixels(bytes: &[u8], format: ImageFormat) -> Result<RgbaImage, ImageError> {
+    match format {
+        #[cfg(feature = "image-png")]
+        ImageFormat::Png => decode_with_image_crate(bytes, ::image::ImageFormat::Png),
+        #[cfg(feature = "image-jpeg")]
+        ImageFormat::Jpeg => decode_with_image_crate(bytes, ::image::ImageFormat::Jpeg),
+        #[cfg(feature = "image-webp")]
+        ImageFormat::WebP => decode_with_image_crate(bytes, ::image::ImageFormat::WebP),
+        #[cfg(feature = "image-qoi")]
+        ImageFormat::Qoi => {
+            let (header, pixels) = qoi::decode_to_vec(bytes).map_err(decode_error)?;
+            let pixels = match header.channels {
+                qoi::Channels::Rgba => pixels,
+                qoi::Channels::Rgb => pixels
+                    .chunks_exact(3)
+                    .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                    .collect(),
+            };
+            RgbaImage::from_raw(header.width, header.height, pixels)
+                .ok_or_else(|| decode_error("pixel data does not match the image's size"))
+        }
+        #[allow(unreachable_patterns)]
+        format => Err(ImageError::FormatDisabled(format)),
+    }
+}
+
+/// Encodes the pixels of an image.
+#[allow(unused_variables)]
+fn encode_pixels(image: &RgbaImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    match format {
+        #[cfg(feature = "image-png")]
+        ImageFormat::Png => encode_with_image_crate(
+            ::image::DynamicImage::ImageRgba8(image.clone()),
+            ::image::ImageOutputFormat::Png,
+        ),
+        #[cfg(feature = "image-jpeg")]
+        ImageFormat::Jpeg => encode_with_image_crate(
+            ::image::DynamicImage::ImageRgb8(
+                ::image::DynamicImage::ImageRgba8(image.clone()).into_rgb8(),
+            ),
+            ::image::ImageOutputFormat::Jpeg(90),
+        ),
+        #[cfg(feature = "image-qoi")]
+        ImageFormat::Qoi => {
+            qoi::encode_to_vec(image.as_raw(), image.width(), image.height()).map_err(encode_error)
+        }
+        #[allow(unreachable_patterns)]
+        format => Err(ImageError::EncodingNotSupported(format)),
+    }
+}
+
+/// Rotates and flips an image according to its EXIF orientation.
+fn apply_orientation(image: RgbaImage, orientation: u16) -> RgbaImage {
+    use ::image::imageops::{flip_horizontal, flip_vertical, rotate180, rotate270, rotate90};
+
+    match orientation {
+        2 => flip_horizontal(&image),
+        3 => rotate180(&image),
+        4 => flip_vertical(&image),
+        5 => flip_horizontal(&rotate90(&image)),
+        6 => rotate90(&image),
+        7 => flip_horizontal(&rotate270(&image)),
+        8 => rotate270(&image),
+        _ => image,
+    }
+}
+
+/// Reads the EXIF orientation of an image file, if it has one.
+fn exif_orientation(bytes: &[u8], format: ImageFormat) -> Option<u16> {
+    let tiff = match format {
+        ImageFormat::Png => png_exif(bytes)?,
+        ImageFormat::Jpeg => jpeg_exif(bytes)?,
+        ImageFormat::WebP => webp_exif(bytes)?,
+        ImageFormat::Qoi => return None,
+    };
+    tiff_orientation(tiff)
+}
+
+/// Finds the EXIF data in the APP1 segment of a JPEG file.
+fn jpeg_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut rest = bytes.strip_prefix(&[0xFF, 0xD8])?;
+    loop {
+        match *rest.get(..2)? {
+            // Fill bytes before a marker.
+            [0xFF, 0xFF] => rest = &rest[1..],
+            // Start of scan or end of image. The EXIF data must come before these.
+            [0xFF, 0xDA | 0xD9] => return None,
+            // Markers without a length.
+            [0xFF, 0x01 | 0xD0..=0xD7] => rest = &rest[2..],
+            [0xFF, marker] => {
+                let length = u16::from_be_bytes([*rest.get(2)?, *rest.get(3)?]) as usize;
+                let segment = rest.get(4..2 + length)?;
+                if marker == 0xE1 {
+                    if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                        return Some(tiff);
+                    }
+                }
+                rest = rest.get(2 + length..)?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Finds the EXIF data in the `eXIf` chunk of a PNG file.
+fn png_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut rest = bytes.strip_prefix(b"\x89PNG\r\n\x1a\n")?;
+    loop {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let kind = rest.get(4..8)?;
+        match kind {
+            b"eXIf" => return rest.get(8..8 + length),
+            // The EXIF chunk must come before the image data.
+            b"IDAT" | b"IEND" => return None,
+            _ => rest = rest.get(12 + length..)?,
+        }
+    }
+}
+
+/// Finds the EXIF data in the `EXIF` chunk of a WebP file.
+fn webp_exif(bytes: &[u8]) -> Option<&[u8]> {
+    if !bytes.starts_with(b"RIFF") || bytes.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let mut rest = bytes.get(12..)?;
+    loop {
+        let kind = rest.get(..4)?;
+        let length = u32::from_le_bytes(rest.get(4..8)?.try_into().ok()?) as usize;
+        if kind == b"EXIF" {
+            let data = rest.get(8..8 + length)?;
+            // Some encoders include the JPEG-style header, some don't.
+            return Some(data.strip_prefix(b"Exif\0\0").unwrap_or(data));
+        }
+        // Chunks are padded to an even length.
+        rest = rest.get(8 + length + (length & 1)..)?;
+    }
+}
+
+/// Reads the orientation tag from the first IFD of TIFF-encoded EXIF data.
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    const ORIENTATION_TAG: u16 = 0x0112;
+
+    let big_endian = match tiff.get(..2)? {
+        b"II" => false,
+        b"MM" => true,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| {
+        let bytes = tiff.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let u32_at = |offset: usize| {
+        let bytes = tiff.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    if u16_at(2)? != 42 {
+        return None;
+    }
+    let ifd = u32_at(4)? as usize;
+    let entry_count = u16_at(ifd)? as usize;
+    (0..entry_count)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION_TAG))
+        // The orientation is a SHORT, stored at the start of the entry's value field.
+        .and_then(|entry| u16_at(entry + 8))
+        .filter(|orientation| (1..=8).contains(orientation))
+}
@@ -1,13 +1,19 @@
 pub mod app;
 pub mod clipboard;
 pub mod config;
+mod dirs;
 mod error;
+mod fs;
 pub mod i18n;
+pub mod image;
+pub mod settings;
+pub mod storage;
 pub mod ui;
 
 pub use app::*;
 pub use config::AppConfig;
 pub use error::*;
+pub use mau_i18n_macros::AppConfig;
 
 // The "inner" main function that does all the work, and can fail.
 //
@@ -0,0 +1,668 @@
+//! Settings screens generated from a description of the config's fields.
+//!
+//! Instead of building a settings UI by hand, apps implement [`Settings`] for their config to
+//! describe which fields can be edited, and how. [`SettingsState`] is a ready-made app state which
+//! renders these fields and edits the config live.
+//!
+//! Labels are [`mau_i18n`] message keys. The labels of a [`FieldKind::Choice`]'s choices are
+//! looked up from attributes of the field's label, so a field labelled `settings-theme` with the
+//! choices `light` and `dark` would be translated like so:
+//!
+//! ```ftl
+//! settings-theme = Theme
+//!     .light = Light
+//!     .dark = Dark
+//! ```
+
+use std::hash::Hash;
+
+use mau_i18n::Language;
+use mau_ui::{
+    AccessibilityTree, Backend, Font, Input, MouseButton, Node, NodeAction, Role, Scope, Scoped,
+    VirtualKeyCode,
+};
+use paws::{rgb, AlignH, AlignV, Color, Layout};
+
+use crate::app::{AppContext, AppSetup, AppState, Ui};
+use crate::clipboard::Clipboard;
+use crate::config::layers;
+use crate::config::AppConfig;
+
+/// The kind of a config field, which determines how it's edited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldKind {
+    /// A boolean, edited with a checkbox.
+    Toggle,
+    /// An integer or floating-point number within the given range, edited by stepping it up and
+    /// down.
+    Number { min: f64, max: f64, step: f64 },
+    /// A string which must be one of the given choices.
+    Choice(&'static [&'static str]),
+    /// A freeform string.
+    Text,
+}
+
+/// A description of a single config field shown on the settings screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SettingsField {
+    /// The dot-separated path to the field in the config, eg. `brush.size`.
+    pub path: &'static str,
+    /// The key of the translated label.
+    pub label: &'static str,
+    /// How the field is edited.
+    pub kind: FieldKind,
+}
+
+impl SettingsField {
+    /// Describes a boolean field.
+    pub const fn toggle(path: &'static str, label: &'static str) -> Self {
+        Self {
+            path,
+            label,
+            kind: FieldKind::Toggle,
+        }
+    }
+
+    /// Describes a numeric field.
+    pub const fn number(
+        path: &'static str,
+        label: &'static str,
+        min: f64,
+        max: f64,
+        step: f64,
+    ) -> Self {
+        Self {
+            path,
+            label,
+            kind: FieldKind::Number { min, max, step },
+        }
+    }
+
+    /// Describes a string field with a fixed set of choices.
+    pub const fn choice(
+        path: &'static str,
+        label: &'static str,
+        choices: &'static [&'static str],
+    ) -> Self {
+        Self {
+            path,
+            label,
+            kind: FieldKind::Choice(choices),
+        }
+    }
+
+    /// Describes a freeform string field.
+    pub const fn text(path: &'static str, label: &'static str) -> Self {
+        Self {
+            path,
+            label,
+            kind: FieldKind::Text,
+        }
+    }
+}
+
+/// A config that can be edited on a generated settings screen.
+pub trait Settings: AppConfig {
+    /// Returns descriptions of the fields that should be shown on the settings screen, in the
+    /// order they should be shown in.
+    fn settings_fields() -> Vec<SettingsField>;
+}
+
+/// The config's values, serialized once when the settings screen is first shown and edited in
+/// place, so that the whole config doesn't have to be converted for every field on every frame.
+struct Values {
+    value: toml::Value,
+    changed: bool,
+}
+
+impl Values {
+    fn new<C>(config: &C) -> Self
+    where
+        C: AppConfig,
+    {
+        Self {
+            value: toml::Value::try_from(config).unwrap_or_else(|_| layers::empty()),
+            changed: false,
+        }
+    }
+
+    /// Returns the value under the given path.
+    fn get(&self, path: &str) -> Option<toml::Value> {
+        layers::get(&self.value, path).cloned()
+    }
+
+    /// Sets the value under the given path. The change is written to the config once
+    /// [`commit`][Self::commit] is called.
+    fn set(&mut self, path: &str, new_value: toml::Value) {
+        layers::set(&mut self.value, path, new_value);
+        self.changed = true;
+    }
+
+    /// Writes the values back to the config if they changed, and schedules the config to be
+    /// saved. Values that don't fit the config's structure are rejected with a warning.
+    fn commit<C>(&mut self, config: &mut C)
+    where
+        C: AppConfig,
+    {
+        if !self.changed {
+            return;
+        }
+        self.changed = false;
+        match self.value.clone().try_into::<C>() {
+            Ok(updated) => config.write(|config| *config = updated),
+            Err(error) => {
+                log::warn!("cannot set config fields: {}", error);
+                *self = Self::new(config);
+            }
+        }
+    }
+}
+
+/// The colors used by the settings screen.
+#[derive(Debug, Clone, Copy)]
+pub struct SettingsColors {
+    pub background: Color,
+    pub row: Color,
+    pub hover: Color,
+    pub text: Color,
+    pub accent: Color,
+}
+
+impl Default for SettingsColors {
+    fn default() -> Self {
+        Self {
+            background: rgb(0xf5, 0xf5, 0xf5),
+            row: rgb(0xff, 0xff, 0xff),
+            hover: rgb(0xe8, 0xe8, 0xe8),
+            text: rgb(0x1f, 0x1f, 0x1f),
+            accent: rgb(0x25, 0x63, 0xeb),
+        }
+    }
+}
+
+const ROW_HEIGHT: f32 = 32.0;
+const BUTTON_WIDTH: f32 = 96.0;
+const SPACING: f32 = 8.0;
+
+/// A settings screen, which renders the fields described by the config's [`Settings`]
+/// implementation and edits them live.
+///
+/// The screen is closed by pressing Escape or clicking the back button, after which it
+/// transitions back to the state it was opened from.
+pub struct SettingsState<T, E>
+where
+    T: AppSetup,
+{
+    previous: Option<Box<dyn AppState<T, Error = E>>>,
+    font: Font,
+    language: Language,
+    colors: SettingsColors,
+    title: &'static str,
+    fields: Vec<SettingsField>,
+    defaults: Option<toml::Value>,
+    values: Option<Values>,
+    editing: Option<usize>,
+    closed: bool,
+}
+
+impl<T, E> SettingsState<T, E>
+where
+    T: AppSetup,
+    T::Config: Settings,
+{
+    /// Creates a settings screen, which returns to the `previous` state once it's closed.
+    pub fn new(previous: Box<dyn AppState<T, Error = E>>, font: Font, language: Language) -> Self {
+        Self {
+            previous: Some(previous),
+            font,
+            language,
+            colors: SettingsColors::default(),
+            title: "settings-title",
+            fields: T::Config::settings_fields(),
+            defaults: toml::Value::try_from(T::Config::default()).ok(),
+            values: None,
+            editing: None,
+            closed: false,
+        }
+    }
+
+    /// Sets the colors used by the settings screen.
+    pub fn with_colors(mut self, colors: SettingsColors) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Sets the key of the translated title. Defaults to `settings-title`.
+    pub fn with_title(mut self, title: &'static str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Returns the default value of the field.
+    fn default_value(&self, field: &SettingsField) -> Option<toml::Value> {
+        self.defaults
+            .as_ref()
+            .and_then(|defaults| layers::get(defaults, field.path))
+            .cloned()
+    }
+
+    /// Resets all fields shown on the screen to their default values.
+    fn reset_all(&mut self, values: &mut Values) {
+        for field in &self.fields {
+            if let Some(default) = self.default_value(field) {
+                values.set(field.path, default);
+            }
+        }
+        self.editing = None;
+    }
+
+    /// Processes a button with the given label, and returns whether it was clicked.
+    fn button(&self, ui: &mut Ui, input: &Input, label: &str, width: f32) -> bool {
+        ui.push((width, ui.height()), Layout::Freeform);
+        let hovered = is_hovered(ui, input);
+        let clicked = hovered && input.mouse_button_just_pressed(MouseButton::Left);
+        ui.fill_rounded(
+            if hovered {
+                self.colors.hover
+            } else {
+                self.colors.row
+            },
+            4.0,
+        );
+        ui.outline_rounded(self.colors.accent, 4.0, 1.0);
+        ui.text(
+            &self.font,
+            label,
+            self.colors.accent,
+            (AlignH::Center, AlignV::Middle),
+        );
+        ui.pop();
+        clicked
+    }
+
+    /// Processes a button, and exposes it to assistive technology under the given key. Returns
+    /// whether it was clicked, either with the mouse or by an action request.
+    fn accessible_button(
+        &self,
+        ui: &mut Ui,
+        input: &Input,
+        accessibility: &mut AccessibilityTree,
+        label: &str,
+        key: impl Hash,
+        width: f32,
+    ) -> bool {
+        ui.push((width, ui.height()), Layout::Freeform);
+        let bounds = ui.rect();
+        let clicked = self.button(ui, input, label, width);
+        ui.pop();
+        let id = accessibility.leaf(
+            Node::new(Role::Button, label)
+                .with_bounds(bounds)
+                .with_key(key)
+                .with_action(NodeAction::Click),
+        );
+        clicked || accessibility.action_requested(id, NodeAction::Click)
+    }
+
+    /// Performs an action requested by assistive technology on a field.
+    fn field_action(&mut self, index: usize, action: NodeAction, values: &mut Values) {
+        let field = self.fields[index];
+        let value = match values.get(field.path) {
+            Some(value) => value,
+            None => return,
+        };
+        match (field.kind, action) {
+            (FieldKind::Toggle, NodeAction::Click) => {
+                let checked = value.as_bool().unwrap_or(false);
+                values.set(field.path, toml::Value::Boolean(!checked));
+            }
+            (FieldKind::Number { min, max, step }, NodeAction::Increment) => {
+                values.set(field.path, step_number(&value, step, min, max));
+            }
+            (FieldKind::Number { min, max, step }, NodeAction::Decrement) => {
+                values.set(field.path, step_number(&value, -step, min, max));
+            }
+            (FieldKind::Choice(choices), NodeAction::Click) => {
+                let current = value.as_str().unwrap_or_default();
+                if let Some(next) = next_choice(choices, current, false) {
+                    values.set(field.path, toml::Value::String(next.to_owned()));
+                }
+            }
+            (FieldKind::Text, NodeAction::Focus) => self.editing = Some(index),
+            _ => (),
+        }
+    }
+
+    /// Processes the control used for editing a field's value.
+    fn field_control(
+        &mut self,
+        index: usize,
+        ui: &mut Ui,
+        input: &mut Input,
+        values: &mut Values,
+        clipboard: &Clipboard,
+    ) -> Option<String> {
+        let field = self.fields[index];
+        let value = values.get(field.path)?;
+        let width = ui.remaining_width() - BUTTON_WIDTH - SPACING;
+        ui.push((width, ui.height()), Layout::Horizontal);
+        let hovered = is_hovered(ui, input);
+        let clicked = hovered && input.mouse_button_just_pressed(MouseButton::Left);
+        let text = match field.kind {
+            FieldKind::Toggle => {
+                let checked = value.as_bool().unwrap_or(false);
+                if clicked {
+                    values.set(field.path, toml::Value::Boolean(!checked));
+                }
+                ui.push((ROW_HEIGHT, ROW_HEIGHT), Layout::Freeform);
+                ui.pad(8.0);
+                if checked {
+                    ui.fill_rounded(self.colors.accent, 2.0);
+                } else {
+                    ui.outline_rounded(self.colors.text, 2.0, 1.0);
+                }
+                ui.pop();
+                checked.to_string()
+            }
+            FieldKind::Number { min, max, step } => {
+                let number = match value {
+                    toml::Value::Integer(i) => i as f64,
+                    toml::Value::Float(f) => f,
+                    _ => 0.0,
+                };
+                let mut delta = 0.0;
+                if self.button(ui, input, "-", ROW_HEIGHT) {
+                    delta -= step;
+                }
+                ui.space(SPACING);
+                if self.button(ui, input, "+", ROW_HEIGHT) {
+                    delta += step;
+                }
+                if hovered {
+                    if let Some(scroll) = input.action(mau_ui::MouseScroll).1 {
+                        delta += step * scroll.y.signum() as f64;
+                    }
+                }
+                if delta != 0.0 {
+                    values.set(field.path, step_number(&value, delta, min, max));
+                }
+                let text = match value {
+                    toml::Value::Integer(i) => i.to_string(),
+                    _ => format!("{number:.2}"),
+                };
+                ui.space(SPACING);
+                ui.push((ui.remaining_width(), ui.height()), Layout::Freeform);
+                ui.text(
+                    &self.font,
+                    &text,
+                    self.colors.text,
+                    (AlignH::Left, AlignV::Middle),
+                );
+                ui.pop();
+                text
+            }
+            FieldKind::Choice(choices) => {
+                let current = value.as_str().unwrap_or_default();
+                if clicked {
+                    if let Some(next) = next_choice(choices, current, input.shift_is_down()) {
+                        values.set(field.path, toml::Value::String(next.to_owned()));
+                    }
+                }
+                let text = self
+                    .language
+                    .message(&format!("{}.{}", field.label, current));
+                ui.text(
+                    &self.font,
+                    &text,
+                    self.colors.text,
+                    (AlignH::Left, AlignV::Middle),
+                );
+                text
+            }
+            FieldKind::Text => {
+                let mut text = value.as_str().unwrap_or_default().to_owned();
+                let pasted = hovered
+                    .then(|| clipboard.middle_click_paste(input))
+                    .flatten();
+                if clicked || pasted.is_some() {
+                    self.editing = Some(index);
+                } else if input.mouse_button_just_pressed(MouseButton::Left)
+                    && self.editing == Some(index)
+                {
+                    self.editing = None;
+                }
+                if self.editing == Some(index) {
+                    input.enter_scope(Scope::TextInput);
+                    let mut changed = false;
+                    if let Some(pasted) = pasted {
+                        text.push_str(&pasted);
+                        changed = true;
+                    }
+                    for &c in input.characters_typed() {
+                        if !c.is_control() {
+                            text.push(c);
+                            changed = true;
+                        }
+                    }
+                    if input
+                        .action(Scoped(Scope::TextInput, VirtualKeyCode::Back))
+                        .1
+                    {
+                        changed |= text.pop().is_some();
+                    }
+                    if input
+                        .action(Scoped(Scope::TextInput, VirtualKeyCode::Return))
+                        .1
+                    {
+                        self.editing = None;
+                    }
+                    if changed {
+                        values.set(field.path, toml::Value::String(text.clone()));
+                    }
+                    ui.outline_rounded(self.colors.accent, 4.0, 1.0);
+                }
+                ui.push((ui.remaining_width(), ui.height()), Layout::Freeform);
+                ui.pad((8.0, 0.0));
+                ui.text(
+                    &self.font,
+                    &text,
+                    self.colors.text,
+                    (AlignH::Left, AlignV::Middle),
+                );
+                ui.pop();
+                text
+            }
+        };
+        ui.pop();
+        Some(text)
+    }
+}
+
+/// Steps a number field's value by `delta`, keeping it within bounds and preserving whether it's
+/// an integer.
+fn step_number(value: &toml::Value, delta: f64, min: f64, max: f64) -> toml::Value {
+    let number = match *value {
+        toml::Value::Integer(i) => i as f64,
+        toml::Value::Float(f) => f,
+        _ => 0.0,
+    };
+    let number = (number + delta).clamp(min, max);
+    match value {
+        toml::Value::Integer(_) => toml::Value::Integer(number.round() as i64),
+        _ => toml::Value::Float(number),
+    }
+}
+
+/// Returns the choice after (or before, if `backwards` is set) the current one, wrapping around.
+/// An unknown current value is replaced with the first choice.
+fn next_choice<'a>(choices: &[&'a str], current: &str, backwards: bool) -> Option<&'a str> {
+    if choices.is_empty() {
+        return None;
+    }
+    let next = match choices.iter().position(|&choice| choice == current) {
+        Some(i) if backwards => (i + choices.len() - 1) % choices.len(),
+        Some(i) => (i + 1) % choices.len(),
+        None => 0,
+    };
+    Some(choices[next])
+}
+
+/// Returns whether the mouse is inside of the current group.
+fn is_hovered(ui: &Ui, input: &Input) -> bool {
+    let rect = ui.rect();
+    let mouse = input.mouse_position();
+    input.mouse_active()
+        && mouse.x >= rect.left()
+        && mouse.x < rect.right()
+        && mouse.y >= rect.top()
+        && mouse.y < rect.bottom()
+}
+
+impl<T, E> AppState<T> for SettingsState<T, E>
+where
+    T: AppSetup,
+    T::Config: Settings,
+    E: mau_i18n::translate_enum::TranslateEnum + 'static,
+{
+    type Error = E;
+
+    fn process(&mut self, cx: AppContext<T>) -> Result<(), Self::Error> {
+        let AppContext {
+            ui,
+            input,
+            config,
+            clipboard,
+            accessibility,
+            ..
+        } = cx;
+
+        let mut values = self.values.take().unwrap_or_else(|| Values::new(&*config));
+
+        ui.push(ui.size(), Layout::Vertical);
+        ui.fill(self.colors.background);
+        let title = self.language.message(self.title);
+        accessibility.push(Node::new(Role::Dialog, title.as_str()).with_bounds(ui.rect()));
+        ui.pad(16.0);
+
+        // Header
+        ui.push((ui.width(), ROW_HEIGHT), Layout::Horizontal);
+        let back = self.language.message("settings-back");
+        if self.accessible_button(ui, input, accessibility, &back, "back", BUTTON_WIDTH) {
+            self.closed = true;
+        }
+        ui.space(SPACING);
+        ui.push(
+            (ui.remaining_width() - BUTTON_WIDTH - SPACING, ROW_HEIGHT),
+            Layout::Freeform,
+        );
+        ui.text(
+            &self.font,
+            &title,
+            self.colors.text,
+            (AlignH::Left, AlignV::Middle),
+        );
+        ui.pop();
+        ui.space(SPACING);
+        let reset_all = self.language.message("settings-reset-all");
+        if self.accessible_button(
+            ui,
+            input,
+            accessibility,
+            &reset_all,
+            "reset-all",
+            BUTTON_WIDTH,
+        ) {
+            self.reset_all(&mut values);
+        }
+        ui.pop();
+        ui.space(SPACING * 2.0);
+
+        // Fields
+        for index in 0..self.fields.len() {
+            let field = self.fields[index];
+            let label = self.language.message(field.label);
+            ui.push((ui.width(), ROW_HEIGHT), Layout::Horizontal);
+            if is_hovered(ui, input) {
+                ui.fill_rounded(self.colors.hover, 4.0);
+            }
+            let bounds = ui.rect();
+            ui.push((ui.width() * 0.4, ROW_HEIGHT), Layout::Freeform);
+            ui.pad((8.0, 0.0));
+            ui.text(
+                &self.font,
+                &label,
+                self.colors.text,
+                (AlignH::Left, AlignV::Middle),
+            );
+            ui.pop();
+            let value = self.field_control(index, ui, input, &mut values, clipboard);
+            ui.space(SPACING);
+            let reset = self.language.message("settings-reset");
+            let reset_key = (field.path, "reset");
+            if self.accessible_button(ui, input, accessibility, &reset, reset_key, BUTTON_WIDTH) {
+                if let Some(default) = self.default_value(&field) {
+                    values.set(field.path, default);
+                }
+            }
+            ui.pop();
+            ui.space(SPACING);
+
+            let (role, actions): (_, &[NodeAction]) = match field.kind {
+                FieldKind::Toggle => (Role::CheckBox, &[NodeAction::Click]),
+                FieldKind::Number { .. } => (
+                    Role::Slider,
+                    &[NodeAction::Increment, NodeAction::Decrement],
+                ),
+                FieldKind::Choice(_) => (Role::Button, &[NodeAction::Click]),
+                FieldKind::Text => (Role::TextInput, &[NodeAction::Focus]),
+            };
+            let mut node = Node::new(role, label)
+                .with_bounds(bounds)
+                .with_key(field.path)
+                .with_focus(self.editing == Some(index));
+            for &action in actions {
+                node = node.with_action(action);
+            }
+            if let Some(value) = value {
+                node = node.with_value(value);
+            }
+            let id = accessibility.leaf(node);
+            for &action in actions {
+                if accessibility.action_requested(id, action) {
+                    self.field_action(index, action, &mut values);
+                }
+            }
+        }
+
+        if self.editing.is_some() {
+            if input
+                .action(Scoped(Scope::TextInput, VirtualKeyCode::Escape))
+                .1
+            {
+                self.editing = None;
+            }
+        } else if input.action(VirtualKeyCode::Escape).1 {
+            self.closed = true;
+        }
+
+        accessibility.pop();
+        ui.pop();
+
+        values.commit(config);
+        self.values = Some(values);
+        Ok(())
+    }
+
+    fn next_state(
+        mut self: Box<Self>,
+        _renderer: &mut Backend,
+    ) -> Result<Box<dyn AppState<T, Error = Self::Error>>, Self::Error> {
+        match self.previous.take() {
+            Some(previous) if self.closed => Ok(previous),
+            previous => {
+                self.previous = previous;
+                Ok(self)
+            }
+        }
+    }
+}
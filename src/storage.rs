@@ -0,0 +1,348 @@
+//! Persistent app storage, kept separately from the user-editable config.
+//!
+//! The config should only hold settings the user may want to change by hand. Everything else the
+//! app needs to remember between runs belongs in storage:
+//!
+//! - [`Document`]s, which are typed key/value files kept in the data directory, eg. for recently
+//!   opened files or window layouts,
+//! - [`Cache`]s, which hold data that can be regenerated at any time, eg. thumbnails, and are
+//!   kept under a size limit.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use directories::ProjectDirs;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::StorageError;
+
+/// The directories app data and caches are stored in.
+#[derive(Debug, Clone)]
+pub struct Storage {
+    data_dir: PathBuf,
+    cache_dir: PathBuf,
+}
+
+impl Storage {
+    /// Resolves the storage directories of the app with the given name.
+    ///
+    /// Each directory is determined in the following order:
+    ///
+    /// 1. the `MAU_DATA_DIR` or `MAU_CACHE_DIR` environment variable, if it's set,
+    /// 2. the `data` or `cache` directory next to the executable, in portable mode,
+    /// 3. the platform's data or cache directory, eg. `~/.local/share/<app_name>` and
+    ///    `~/.cache/<app_name>` on Linux.
+    ///
+    /// The directories are not created until something is saved into them.
+    pub fn new(app_name: &str) -> Result<Self, StorageError> {
+        let portable_root = crate::dirs::portable_root();
+        let project_dirs = ProjectDirs::from("", "", app_name);
+        let resolve = |variable: &str, portable: &str, platform: fn(&ProjectDirs) -> &Path| {
+            crate::dirs::from_env(variable)
+                .or_else(|| portable_root.as_ref().map(|root| root.join(portable)))
+                .or_else(|| project_dirs.as_ref().map(|dirs| platform(dirs).to_owned()))
+                .ok_or(StorageError::NoStorageDir)
+        };
+        Ok(Self {
+            data_dir: resolve("MAU_DATA_DIR", "data", ProjectDirs::data_dir)?,
+            cache_dir: resolve("MAU_CACHE_DIR", "cache", ProjectDirs::cache_dir)?,
+        })
+    }
+
+    /// Returns the directory app data is stored in.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Returns the directory caches are stored in.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Opens the document with the given name from the data directory. If the document doesn't
+    /// exist yet, an empty one is returned.
+    ///
+    /// The name must be a plain file name; see [`validate_name`].
+    pub fn document(&self, name: &str) -> Result<Document, StorageError> {
+        validate_name(name)?;
+        Document::open(self.data_dir.join(format!("{name}.toml")))
+    }
+
+    /// Opens the cache with the given name, limited to `max_size` bytes.
+    ///
+    /// The name must be a plain file name; see [`validate_name`].
+    pub fn cache(&self, name: &str, max_size: u64) -> Result<Cache, StorageError> {
+        validate_name(name)?;
+        Ok(Cache {
+            dir: self.cache_dir.join(name),
+            max_size,
+        })
+    }
+}
+
+/// Checks that a document or cache name refers to a file directly inside of its storage
+/// directory.
+///
+/// Names must not be empty, `.` or `..`, and must not contain path separators, so that eg.
+/// `../../x` or an absolute path cannot be used to write outside of the app's directories.
+pub fn validate_name(name: &str) -> Result<(), StorageError> {
+    let is_valid = !matches!(name, "" | "." | "..")
+        && !name.contains(['/', '\\', '\0'])
+        && !Path::new(name).has_root()
+        && Path::new(name).components().count() == 1;
+    if is_valid {
+        Ok(())
+    } else {
+        Err(StorageError::InvalidName {
+            name: name.to_owned(),
+        })
+    }
+}
+
+/// A key/value document stored as a TOML file.
+///
+/// Values can be of any serializable type, and are converted on access. Changes are only written
+/// to the disk once [`save`][Self::save] is called.
+pub struct Document {
+    path: PathBuf,
+    values: toml::value::Table,
+}
+
+impl Document {
+    fn open(path: PathBuf) -> Result<Self, StorageError> {
+        let values = match std::fs::read_to_string(&path) {
+            Ok(source) => toml::from_str(&source)?,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => toml::value::Table::new(),
+            Err(error) => return Err(error.into()),
+        };
+        Ok(Self { path, values })
+    }
+
+    /// Returns the path the document is stored at.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the value under the given key, or `None` if there's no such value.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, StorageError>
+    where
+        T: DeserializeOwned,
+    {
+        self.values
+            .get(key)
+            .map(|value| {
+                value
+                    .clone()
+                    .try_into()
+                    .map_err(|error| StorageError::InvalidValue {
+                        key: key.to_owned(),
+                        error,
+                    })
+            })
+            .transpose()
+    }
+
+    /// Sets the value under the given key.
+    pub fn set<T>(&mut self, key: &str, value: &T) -> Result<(), StorageError>
+    where
+        T: Serialize,
+    {
+        self.values
+            .insert(key.to_owned(), toml::Value::try_from(value)?);
+        Ok(())
+    }
+
+    /// Removes the value under the given key.
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+
+    /// Returns whether the document contains a value under the given key.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    /// Writes the document to the disk.
+    pub fn save(&self) -> Result<(), StorageError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let source = toml::to_string(&self.values)?;
+        crate::fs::write_atomically(&self.path, source.as_bytes(), false)?;
+        Ok(())
+    }
+}
+
+/// A list of recently used items, most recent first, holding at most a fixed number of items.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentList<T> {
+    capacity: usize,
+    items: VecDeque<T>,
+}
+
+impl<T> RecentList<T>
+where
+    T: PartialEq,
+{
+    /// Creates an empty list holding at most `capacity` items.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            items: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Marks the item as the most recently used one. If the list is full, the least recently
+    /// used item is dropped.
+    pub fn push(&mut self, item: T) {
+        self.remove(&item);
+        self.items.push_front(item);
+        self.items.truncate(self.capacity);
+    }
+
+    /// Removes the item from the list.
+    pub fn remove(&mut self, item: &T) {
+        self.items.retain(|other| other != item);
+    }
+
+    /// Removes all items from the list.
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Changes the maximum number of items, dropping the least recently used items if needed.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.items.truncate(capacity);
+    }
+
+    /// Returns the maximum number of items.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of items in the list.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns an iterator over the items, most recent first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+}
+
+/// A directory of cached files, limited in size.
+///
+/// Whenever an entry is stored and the total size of the cache exceeds the limit, the entries
+/// that were stored the longest time ago are evicted.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl Cache {
+    /// Returns the directory the cache is stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the path of the entry with the given key.
+    ///
+    /// Keys may contain any characters; anything that isn't safe to use in a file name is
+    /// escaped, including the dots of the keys `.` and `..`.
+    pub fn path(&self, key: &str) -> PathBuf {
+        let mut file_name = String::with_capacity(key.len());
+        let only_dots = key.bytes().all(|byte| byte == b'.');
+        for byte in key.bytes() {
+            match byte {
+                b'.' if only_dots => file_name.push_str("%2E"),
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => {
+                    file_name.push(byte as char)
+                }
+                _ => file_name.push_str(&format!("%{byte:02X}")),
+            }
+        }
+        self.dir.join(file_name)
+    }
+
+    /// Returns the entry with the given key, or `None` if it isn't cached.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match std::fs::read(self.path(key)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Stores an entry, evicting old entries if the cache grows too big.
+    pub fn put(&self, key: &str, contents: &[u8]) -> Result<(), StorageError> {
+        std::fs::create_dir_all(&self.dir)?;
+        crate::fs::write_atomically(&self.path(key), contents, false)?;
+        self.evict()
+    }
+
+    /// Removes the entry with the given key.
+    pub fn remove(&self, key: &str) -> Result<(), StorageError> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&self) -> Result<(), StorageError> {
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the entries in the cache along with their sizes and modification times.
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>, StorageError> {
+        let mut entries = Vec::new();
+        let dir = match std::fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(entries),
+            Err(error) => return Err(error.into()),
+        };
+        for entry in dir {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                entries.push((entry.path(), metadata.len(), modified));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Returns the total size of all entries in the cache, in bytes.
+    pub fn size(&self) -> Result<u64, StorageError> {
+        Ok(self.entries()?.iter().map(|&(_, size, _)| size).sum())
+    }
+
+    /// Evicts the oldest entries until the cache fits within its size limit.
+    pub fn evict(&self) -> Result<(), StorageError> {
+        let mut entries = self.entries()?;
+        let mut size: u64 = entries.iter().map(|&(_, size, _)| size).sum();
+        entries.sort_by_key(|&(_, _, modified)| modified);
+        for (path, entry_size, _) in entries {
+            if size <= self.max_size {
+                break;
+            }
+            log::debug!("evicting cache entry {:?}", path);
+            std::fs::remove_file(&path)?;
+            size -= entry_size;
+        }
+        Ok(())
+    }
+}